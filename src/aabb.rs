@@ -0,0 +1,97 @@
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use std::mem::swap;
+
+// an axis-aligned bounding box, stored as its minimum and maximum corners
+#[derive(Debug, Copy, Clone)]
+pub struct Aabb {
+    pub minimum: Point3,
+    pub maximum: Point3,
+}
+
+impl Aabb {
+    pub fn new(minimum: Point3, maximum: Point3) -> Self {
+        Self { minimum, maximum }
+    }
+
+    // a box containing nothing, which acts as the identity for `surrounding_box`
+    pub fn empty() -> Self {
+        Self {
+            minimum: Point3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            maximum: Point3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        }
+    }
+
+    // the smallest box containing both `self` and `other`
+    pub fn surrounding_box(&self, other: &Self) -> Self {
+        Self {
+            minimum: Point3::new(
+                f64::min(self.minimum.x, other.minimum.x),
+                f64::min(self.minimum.y, other.minimum.y),
+                f64::min(self.minimum.z, other.minimum.z),
+            ),
+            maximum: Point3::new(
+                f64::max(self.maximum.x, other.maximum.x),
+                f64::max(self.maximum.y, other.maximum.y),
+                f64::max(self.maximum.z, other.maximum.z),
+            ),
+        }
+    }
+
+    // the smallest box containing both `self` and the point `p`
+    pub fn include_point(&self, p: Point3) -> Self {
+        self.surrounding_box(&Self::new(p, p))
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        0.5 * (self.minimum + self.maximum)
+    }
+
+    pub fn extent(&self) -> Vec3 {
+        self.maximum - self.minimum
+    }
+
+    // the index of the axis along which the box is widest
+    pub fn longest_axis(&self) -> usize {
+        let e = self.extent();
+        if e.x > e.y && e.x > e.z {
+            0
+        } else if e.y > e.z {
+            1
+        } else {
+            2
+        }
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let e = self.extent();
+        if e.x < 0.0 || e.y < 0.0 || e.z < 0.0 {
+            return 0.0;
+        }
+        2.0 * (e.x * e.y + e.y * e.z + e.z * e.x)
+    }
+
+    // slab test: does the ray pass through the box anywhere in (t_min, t_max)?
+    pub fn hit(&self, ray: &Ray, mut t_min: f64, mut t_max: f64) -> bool {
+        for axis in 0..3 {
+            let inv_d = 1.0 / ray.dir[axis];
+            let mut t0 = (self.minimum[axis] - ray.origin[axis]) * inv_d;
+            let mut t1 = (self.maximum[axis] - ray.origin[axis]) * inv_d;
+            if inv_d < 0.0 {
+                swap(&mut t0, &mut t1);
+            }
+            // written so that a NaN (ray parallel to and on a slab boundary) leaves the
+            // interval untouched rather than rejecting the hit
+            if t0 > t_min {
+                t_min = t0;
+            }
+            if t1 < t_max {
+                t_max = t1;
+            }
+            if t_max <= t_min {
+                return false;
+            }
+        }
+        return true;
+    }
+}
@@ -0,0 +1,83 @@
+use crate::aabb::Aabb;
+use crate::aarect::AaRect;
+use crate::flip_face::FlipFace;
+use crate::hit_record::HitRecord;
+use crate::hittable::Hittable;
+use crate::hittable_list::HittableList;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use rand::RngCore;
+use std::sync::Arc;
+
+// an axis-aligned box, made of six rectangles whose front faces all point outwards
+pub struct AaBox {
+    minimum: Point3,
+    maximum: Point3,
+    sides: HittableList,
+}
+
+impl AaBox {
+    pub fn new(p0: Point3, p1: Point3, mat_ptr: Arc<dyn Material + Send + Sync>) -> Self {
+        let min = Point3::new(
+            f64::min(p0.x, p1.x),
+            f64::min(p0.y, p1.y),
+            f64::min(p0.z, p1.z),
+        );
+        let max = Point3::new(
+            f64::max(p0.x, p1.x),
+            f64::max(p0.y, p1.y),
+            f64::max(p0.z, p1.z),
+        );
+
+        let mut sides = HittableList::new();
+        let m = || Arc::clone(&mat_ptr);
+        sides.add(Box::new(AaRect::xy(min.x, max.x, min.y, max.y, max.z, m())));
+        sides.add(Box::new(FlipFace {
+            object: Box::new(AaRect::xy(min.x, max.x, min.y, max.y, min.z, m())),
+        }));
+        sides.add(Box::new(AaRect::xz(min.x, max.x, min.z, max.z, max.y, m())));
+        sides.add(Box::new(FlipFace {
+            object: Box::new(AaRect::xz(min.x, max.x, min.z, max.z, min.y, m())),
+        }));
+        sides.add(Box::new(AaRect::yz(min.y, max.y, min.z, max.z, max.x, m())));
+        sides.add(Box::new(FlipFace {
+            object: Box::new(AaRect::yz(min.y, max.y, min.z, max.z, min.x, m())),
+        }));
+
+        Self {
+            minimum: min,
+            maximum: max,
+            sides,
+        }
+    }
+}
+
+impl Hittable for AaBox {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        record: &mut HitRecord,
+        gen: &mut dyn RngCore,
+    ) -> bool {
+        self.sides.hit(ray, t_min, t_max, record, gen)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::new(self.minimum, self.maximum))
+    }
+
+    fn is_light(&self) -> bool {
+        self.sides.is_light()
+    }
+
+    fn pdf_value(&self, ray: &Ray, gen: &mut dyn RngCore) -> f64 {
+        self.sides.pdf_value(ray, gen)
+    }
+
+    fn random(&self, origin: Point3, gen: &mut dyn RngCore) -> Vec3 {
+        self.sides.random(origin, gen)
+    }
+}
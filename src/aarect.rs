@@ -0,0 +1,149 @@
+use crate::aabb::Aabb;
+use crate::hit_record::HitRecord;
+use crate::hittable::{area_pdf_to_solid_angle, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use rand::{Rng, RngCore};
+use std::sync::Arc;
+
+// an axis-aligned rectangle, lying in the plane where the `normal_axis` co-ordinate is `k` and
+// spanning [a0, a1] x [b0, b1] along the other two axes. the front face is the side the positive
+// `normal_axis` points towards.
+pub struct AaRect {
+    normal_axis: usize,
+    a_axis: usize,
+    b_axis: usize,
+    pub a0: f64,
+    pub a1: f64,
+    pub b0: f64,
+    pub b1: f64,
+    pub k: f64,
+    pub mat_ptr: Arc<dyn Material + Send + Sync>,
+}
+
+impl AaRect {
+    // a rectangle in the plane z = k
+    pub fn xy(
+        x0: f64,
+        x1: f64,
+        y0: f64,
+        y1: f64,
+        k: f64,
+        mat_ptr: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        Self::new(2, 0, 1, (x0, x1, y0, y1), k, mat_ptr)
+    }
+
+    // a rectangle in the plane y = k
+    pub fn xz(
+        x0: f64,
+        x1: f64,
+        z0: f64,
+        z1: f64,
+        k: f64,
+        mat_ptr: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        Self::new(1, 0, 2, (x0, x1, z0, z1), k, mat_ptr)
+    }
+
+    // a rectangle in the plane x = k
+    pub fn yz(
+        y0: f64,
+        y1: f64,
+        z0: f64,
+        z1: f64,
+        k: f64,
+        mat_ptr: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        Self::new(0, 1, 2, (y0, y1, z0, z1), k, mat_ptr)
+    }
+
+    fn new(
+        normal_axis: usize,
+        a_axis: usize,
+        b_axis: usize,
+        (a0, a1, b0, b1): (f64, f64, f64, f64),
+        k: f64,
+        mat_ptr: Arc<dyn Material + Send + Sync>,
+    ) -> Self {
+        Self {
+            normal_axis,
+            a_axis,
+            b_axis,
+            a0: f64::min(a0, a1),
+            a1: f64::max(a0, a1),
+            b0: f64::min(b0, b1),
+            b1: f64::max(b0, b1),
+            k,
+            mat_ptr,
+        }
+    }
+
+    // the point with the given co-ordinates along the `a`, `b` and normal axes
+    fn point(&self, a: f64, b: f64, n: f64) -> Vec3 {
+        let mut xyz = [0.0; 3];
+        xyz[self.a_axis] = a;
+        xyz[self.b_axis] = b;
+        xyz[self.normal_axis] = n;
+        Vec3::new(xyz[0], xyz[1], xyz[2])
+    }
+}
+
+impl Hittable for AaRect {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        record: &mut HitRecord,
+        _gen: &mut dyn RngCore,
+    ) -> bool {
+        let n = self.normal_axis;
+        let t = (self.k - ray.origin[n]) / ray.dir[n];
+        // written to also reject the NaN produced by a ray parallel to the rectangle
+        if !(t >= t_min && t <= t_max) {
+            return false;
+        }
+        let a = ray.origin[self.a_axis] + t * ray.dir[self.a_axis];
+        let b = ray.origin[self.b_axis] + t * ray.dir[self.b_axis];
+        if a < self.a0 || a > self.a1 || b < self.b0 || b > self.b1 {
+            return false;
+        }
+
+        record.t = t;
+        record.p = ray.at(t);
+        record.u = (a - self.a0) / (self.a1 - self.a0);
+        record.v = (b - self.b0) / (self.b1 - self.b0);
+        record.mat_ptr = Some(Arc::clone(&self.mat_ptr));
+        record.set_face_normal(ray, self.point(0.0, 0.0, 1.0));
+        return true;
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        // pad the box in the normal direction, so that it has some thickness
+        Some(Aabb::new(
+            self.point(self.a0, self.b0, self.k - 1e-4),
+            self.point(self.a1, self.b1, self.k + 1e-4),
+        ))
+    }
+
+    fn is_light(&self) -> bool {
+        self.mat_ptr.is_emissive()
+    }
+
+    fn pdf_value(&self, ray: &Ray, gen: &mut dyn RngCore) -> f64 {
+        let mut record = HitRecord::dummy();
+        if !self.hit(ray, 0.001, f64::INFINITY, &mut record, gen) {
+            return 0.0;
+        }
+        let area = (self.a1 - self.a0) * (self.b1 - self.b0);
+        return area_pdf_to_solid_angle(ray, record.t, record.normal, area);
+    }
+
+    fn random(&self, origin: Point3, gen: &mut dyn RngCore) -> Vec3 {
+        let a = self.a0 + gen.gen::<f64>() * (self.a1 - self.a0);
+        let b = self.b0 + gen.gen::<f64>() * (self.b1 - self.b0);
+        return self.point(a, b, self.k) - origin;
+    }
+}
@@ -0,0 +1,49 @@
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::util::solve_quadratic;
+use crate::vec3::Point3;
+use rand::{Rng, RngCore};
+use std::sync::Arc;
+
+// a haze of even density filling a large ball around the scene, through which every ray
+// travels. unlike a `ConstantMedium`, it surrounds the camera and the objects in the scene, and
+// rays only escape to the background once they leave the ball.
+pub struct Atmosphere {
+    pub density: f64,
+    // how light is scattered at each point in the haze
+    pub phase_function: Arc<dyn Material + Send + Sync>,
+    pub center: Point3,
+    pub radius: f64,
+}
+
+impl Atmosphere {
+    // pick the point (as a distance `t` along the ray) where the ray is scattered on its way
+    // through [t_min, t_max], or `None` if it gets through unscattered
+    pub fn sample_scatter(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        gen: &mut dyn RngCore,
+    ) -> Option<f64> {
+        // the part of the ray inside the ball
+        let oc = ray.origin - self.center;
+        let (t_enter, t_exit) = solve_quadratic(
+            ray.dir.length_squared(),
+            2.0 * ray.dir.dot(&oc),
+            oc.length_squared() - self.radius * self.radius,
+        )?;
+        let t0 = f64::max(t_enter, t_min);
+        let t1 = f64::min(t_exit, t_max);
+        if t0 >= t1 {
+            return None;
+        }
+
+        let ray_length = ray.dir.length();
+        let hit_distance = -(1.0 - gen.gen::<f64>()).ln() / self.density;
+        if hit_distance > (t1 - t0) * ray_length {
+            return None;
+        }
+        return Some(t0 + hit_distance / ray_length);
+    }
+}
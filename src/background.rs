@@ -0,0 +1,113 @@
+use crate::image::{Image, WrapMode};
+use crate::onb::Onb;
+use crate::random::random_in_cone;
+use crate::util::degrees_to_radians;
+use crate::vec3::{Color, Vec3};
+use rand::RngCore;
+use std::f64::consts::PI;
+
+// the light arriving along rays which escape the scene without hitting anything
+pub enum Background {
+    Solid(Color),
+    // a blend from `bottom` (looking straight down) to `top` (looking straight up)
+    Gradient { bottom: Color, top: Color },
+    EnvironmentMap(EnvironmentMap),
+}
+
+impl Default for Background {
+    // the white-to-blue sky from "Ray Tracing in One Weekend"
+    fn default() -> Self {
+        Background::Gradient {
+            bottom: Color::new(1.0, 1.0, 1.0),
+            top: Color::new(0.5, 0.7, 1.0),
+        }
+    }
+}
+
+impl Background {
+    pub fn color(&self, dir: Vec3) -> Color {
+        match self {
+            Background::Solid(color) => *color,
+            Background::Gradient { bottom, top } => {
+                // shade according to the y-component of the normalised ray direction
+                let unit_direction = dir.unit_vector();
+                let t = 0.5 * (unit_direction.y + 1.0);
+                (1.0 - t) * *bottom + t * *top
+            }
+            Background::EnvironmentMap(map) => map.lookup(dir),
+        }
+    }
+}
+
+// an equirectangular (latitude-longitude) image surrounding the scene. the top row of the image
+// is straight up (+y), and the centre of the image looks down -z.
+pub struct EnvironmentMap {
+    image: Image,
+    intensity: f64,
+    rotation: f64, // about the y axis, in radians
+}
+
+impl EnvironmentMap {
+    // `rotation` turns the map about the vertical axis, in degrees
+    pub fn new(image: Image, intensity: f64, rotation: f64) -> Self {
+        Self {
+            image,
+            intensity,
+            rotation: degrees_to_radians(rotation),
+        }
+    }
+
+    pub fn lookup(&self, dir: Vec3) -> Color {
+        let d = dir.unit_vector();
+        let phi = f64::atan2(d.x, -d.z) - self.rotation;
+        let theta = f64::acos(d.y.clamp(-1.0, 1.0));
+        let u = (phi / (2.0 * PI) + 0.5).rem_euclid(1.0);
+        let v = theta / PI;
+        // the map wraps around horizontally, and is clamped at the poles
+        let color = self.image.bilinear(u, v, WrapMode::Repeat, WrapMode::Clamp);
+        return self.intensity * color;
+    }
+}
+
+// a distant disk of light, such as the sun, added on top of the background. it is small and
+// bright, so it is sampled directly rather than left for rays to find by chance.
+pub struct Sun {
+    direction: Vec3,
+    // the cosine of the angle between the centre of the disk and its edge
+    cos_max: f64,
+    radiance: Color,
+}
+
+impl Sun {
+    // a sun `angle` degrees across, in the given `direction` (towards the sun), which lights a
+    // surface facing it with the given `irradiance`
+    pub fn new(direction: Vec3, angle: f64, irradiance: Color) -> Self {
+        let cos_max = f64::cos(degrees_to_radians(angle / 2.0));
+        let solid_angle = 2.0 * PI * (1.0 - cos_max);
+        Self {
+            direction: direction.unit_vector(),
+            cos_max,
+            radiance: irradiance / solid_angle,
+        }
+    }
+
+    pub fn radiance(&self, dir: Vec3) -> Color {
+        match dir.unit_vector().dot(&self.direction) >= self.cos_max {
+            true => self.radiance,
+            false => Color::zeroes(),
+        }
+    }
+
+    // the probability density with which `random` chooses `dir`
+    pub fn pdf(&self, dir: Vec3) -> f64 {
+        match dir.unit_vector().dot(&self.direction) >= self.cos_max {
+            true => 1.0 / (2.0 * PI * (1.0 - self.cos_max)),
+            false => 0.0,
+        }
+    }
+
+    // a random direction towards the disk
+    pub fn random(&self, gen: &mut dyn RngCore) -> Vec3 {
+        Onb::from_w(self.direction).to_world(random_in_cone(self.cos_max, gen))
+    }
+}
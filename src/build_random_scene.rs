@@ -3,25 +3,25 @@ use crate::hittable_list::HittableList;
 use crate::lambertian::Lambertian;
 use crate::material::Material;
 use crate::metal::Metal;
+use crate::plane::Plane;
 use crate::sphere::Sphere;
+use crate::texture::solid;
 use crate::vec3::{Color, Point3, Vec3};
 use rand::Rng;
 use std::sync::Arc;
 
 type ArcMaterial = Arc<dyn Material + Send + Sync>;
 
-pub fn build_random_scene() -> HittableList {
+pub fn build_random_scene<R: Rng + ?Sized>(gen: &mut R) -> HittableList {
     let mut world = HittableList::new();
 
-    let mut gen = rand::thread_rng();
-
     // ground
     let ground_mat: ArcMaterial = Arc::new(Lambertian {
-        albedo: Color::new(0.5, 0.5, 0.5),
+        albedo: solid(Color::new(0.5, 0.5, 0.5)),
     });
-    let ground = Sphere {
-        center: Point3::new(0.0, -1000.0, 0.0),
-        radius: 1000.0,
+    let ground = Plane {
+        point: Point3::zeroes(),
+        normal: Vec3::new(0.0, 1.0, 0.0),
         mat_ptr: ground_mat,
     };
     world.add(Box::new(ground));
@@ -41,16 +41,21 @@ pub fn build_random_scene() -> HittableList {
                 let sphere_mat: ArcMaterial;
                 if choose_mat < 0.8 {
                     // diffuse
-                    let albedo = Color::random(&mut gen) * Color::random(&mut gen);
-                    sphere_mat = Arc::new(Lambertian { albedo });
+                    let albedo = Color::random(gen) * Color::random(gen);
+                    sphere_mat = Arc::new(Lambertian {
+                        albedo: solid(albedo),
+                    });
                 } else if choose_mat < 0.95 {
                     // metal
-                    let albedo = Color::random(&mut gen) * 0.5 + 0.5; // [0.5, 1)
+                    let albedo = Color::random(gen) * 0.5 + 0.5; // [0.5, 1)
                     let fuzz = gen.gen::<f64>() * 0.5;
-                    sphere_mat = Arc::new(Metal { albedo, fuzz });
+                    sphere_mat = Arc::new(Metal {
+                        albedo: solid(albedo),
+                        fuzz,
+                    });
                 } else {
                     // glass
-                    sphere_mat = Arc::new(Dielectric { ir: 1.5 });
+                    sphere_mat = Arc::new(Dielectric::new(1.5));
                 }
 
                 let sphere = Sphere {
@@ -63,7 +68,7 @@ pub fn build_random_scene() -> HittableList {
         }
     }
 
-    let mat1: ArcMaterial = Arc::new(Dielectric { ir: 1.5 });
+    let mat1: ArcMaterial = Arc::new(Dielectric::new(1.5));
     let sphere1 = Sphere {
         center: Vec3::new(0.0, 1.0, 0.0),
         radius: 1.0,
@@ -72,7 +77,7 @@ pub fn build_random_scene() -> HittableList {
     world.add(Box::new(sphere1));
 
     let mat2: ArcMaterial = Arc::new(Lambertian {
-        albedo: Vec3::new(0.4, 0.2, 0.1),
+        albedo: solid(Color::new(0.4, 0.2, 0.1)),
     });
     let sphere2 = Sphere {
         center: Vec3::new(-4.0, 1.0, 0.0),
@@ -82,7 +87,7 @@ pub fn build_random_scene() -> HittableList {
     world.add(Box::new(sphere2));
 
     let mat3: ArcMaterial = Arc::new(Metal {
-        albedo: Vec3::new(0.7, 0.6, 0.5),
+        albedo: solid(Color::new(0.7, 0.6, 0.5)),
         fuzz: 0.0,
     });
     let sphere3 = Sphere {
@@ -0,0 +1,195 @@
+use crate::aabb::Aabb;
+use crate::hit_record::HitRecord;
+use crate::hittable::Hittable;
+use crate::ray::Ray;
+use crate::vec3::Point3;
+use rand::RngCore;
+use std::sync::Arc;
+
+// nodes with this many objects or fewer are not split any further
+const MAX_LEAF_SIZE: usize = 4;
+
+// number of buckets used to approximate the surface area heuristic along the split axis
+const NUM_BINS: usize = 12;
+
+// a bounding volume hierarchy: a binary tree of boxes, where each box encloses every object
+// beneath it. rays which miss a box can skip its entire subtree.
+pub struct BvhNode {
+    bbox: Aabb,
+    contents: BvhContents,
+}
+
+enum BvhContents {
+    Leaf(Vec<Arc<dyn Hittable>>),
+    Split {
+        axis: usize,
+        left: Box<BvhNode>,
+        right: Box<BvhNode>,
+    },
+}
+
+// an object along with its (cached) bounds, used while building the tree
+struct Primitive {
+    object: Arc<dyn Hittable>,
+    bbox: Aabb,
+    centroid: Point3,
+}
+
+impl BvhNode {
+    // build a hierarchy over `objects`, all of which must be bounded.
+    // use `HittableList::into_bvh` for lists which may contain unbounded objects.
+    pub fn new(objects: Vec<Arc<dyn Hittable>>) -> Self {
+        let primitives = objects
+            .into_iter()
+            .map(|object| {
+                let bbox = object
+                    .bounding_box()
+                    .expect("BvhNode cannot contain unbounded objects");
+                Primitive {
+                    object,
+                    bbox,
+                    centroid: bbox.centroid(),
+                }
+            })
+            .collect();
+        Self::build(primitives)
+    }
+
+    fn build(mut primitives: Vec<Primitive>) -> Self {
+        let bbox = primitives
+            .iter()
+            .fold(Aabb::empty(), |b, p| b.surrounding_box(&p.bbox));
+
+        if primitives.len() <= MAX_LEAF_SIZE {
+            return Self::leaf(bbox, primitives);
+        }
+
+        // split along the axis where the centroids are most spread out
+        let centroid_bounds = primitives
+            .iter()
+            .fold(Aabb::empty(), |b, p| b.include_point(p.centroid));
+        let axis = centroid_bounds.longest_axis();
+        let lo = centroid_bounds.minimum[axis];
+        let width = centroid_bounds.maximum[axis] - lo;
+
+        let (left, right) = if width > 0.0 {
+            Self::partition_sah(primitives, axis, lo, width)
+        } else {
+            // every centroid coincides, so no plane separates them. split the list in half.
+            let right = primitives.split_off(primitives.len() / 2);
+            (primitives, right)
+        };
+
+        Self {
+            bbox,
+            contents: BvhContents::Split {
+                axis,
+                left: Box::new(Self::build(left)),
+                right: Box::new(Self::build(right)),
+            },
+        }
+    }
+
+    // bucket the centroids along `axis`, and split at the bucket boundary which minimises the
+    // surface area heuristic: the sum over both children of (surface area * object count).
+    fn partition_sah(
+        primitives: Vec<Primitive>,
+        axis: usize,
+        lo: f64,
+        width: f64,
+    ) -> (Vec<Primitive>, Vec<Primitive>) {
+        let bin_of = |p: &Primitive| {
+            let b = ((p.centroid[axis] - lo) / width * NUM_BINS as f64) as usize;
+            b.min(NUM_BINS - 1)
+        };
+
+        let mut bin_bounds = [Aabb::empty(); NUM_BINS];
+        let mut bin_counts = [0usize; NUM_BINS];
+        for p in primitives.iter() {
+            let b = bin_of(p);
+            bin_counts[b] += 1;
+            bin_bounds[b] = bin_bounds[b].surrounding_box(&p.bbox);
+        }
+
+        // `costs[i]` is the cost of putting bins 0..=i on the left and the rest on the right
+        let mut costs = [0.0; NUM_BINS - 1];
+        let mut left_box = Aabb::empty();
+        let mut left_count = 0;
+        for i in 0..NUM_BINS - 1 {
+            left_box = left_box.surrounding_box(&bin_bounds[i]);
+            left_count += bin_counts[i];
+            costs[i] += left_box.surface_area() * left_count as f64;
+        }
+        let mut right_box = Aabb::empty();
+        let mut right_count = 0;
+        for i in (1..NUM_BINS).rev() {
+            right_box = right_box.surrounding_box(&bin_bounds[i]);
+            right_count += bin_counts[i];
+            costs[i - 1] += right_box.surface_area() * right_count as f64;
+        }
+
+        let mut best = 0;
+        for i in 1..NUM_BINS - 1 {
+            if costs[i] < costs[best] {
+                best = i;
+            }
+        }
+
+        // the first and last bins are never empty, so both halves are non-empty
+        primitives.into_iter().partition(|p| bin_of(p) <= best)
+    }
+
+    fn leaf(bbox: Aabb, primitives: Vec<Primitive>) -> Self {
+        Self {
+            bbox,
+            contents: BvhContents::Leaf(primitives.into_iter().map(|p| p.object).collect()),
+        }
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        record: &mut HitRecord,
+        gen: &mut dyn RngCore,
+    ) -> bool {
+        if !self.bbox.hit(ray, t_min, t_max) {
+            return false;
+        }
+
+        match &self.contents {
+            BvhContents::Leaf(objects) => {
+                let mut temp_rec: HitRecord = HitRecord::dummy();
+                let mut hit_anything = false;
+                let mut closest_so_far = t_max;
+                for item in objects.iter() {
+                    if item.hit(ray, t_min, closest_so_far, &mut temp_rec, gen) {
+                        hit_anything = true;
+                        closest_so_far = temp_rec.t;
+                        record.copy_from(&temp_rec);
+                    }
+                }
+                return hit_anything;
+            }
+            BvhContents::Split { axis, left, right } => {
+                // visit the child nearest the ray origin first, so that a hit there can cull the
+                // far child with a tighter `t_max`
+                let (first, second) = match ray.dir[*axis] < 0.0 {
+                    true => (right, left),
+                    false => (left, right),
+                };
+                let hit_first = first.hit(ray, t_min, t_max, record, gen);
+                let t_max = if hit_first { record.t } else { t_max };
+                let hit_second = second.hit(ray, t_min, t_max, record, gen);
+                return hit_first || hit_second;
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bbox)
+    }
+}
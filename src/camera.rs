@@ -1,8 +1,8 @@
-use crate::random::random_in_unit_disk;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::util::degrees_to_radians;
 use crate::vec3::{Point3, Vec3};
-use rand::rngs::ThreadRng;
+use crate::warp::concentric_disk;
 
 pub struct Camera {
     origin: Point3,
@@ -13,6 +13,84 @@ pub struct Camera {
     lens_radius: f64,
     u: Vec3,
     v: Vec3,
+    // rays are sent at times spread evenly over [shutter_open, shutter_close]
+    shutter_open: f64,
+    shutter_close: f64,
+}
+
+// the parameters from which a `Camera` is built, kept around so that they can be
+// read from a scene file or overridden before the camera is constructed
+#[derive(Debug, Copy, Clone)]
+pub struct CameraSettings {
+    pub look_from: Point3,
+    pub look_at: Point3,
+    pub vup: Vec3,
+    pub vfov: f64, // vertical field of view, in degrees
+    pub aspect_ratio: f64,
+    pub aperture: f64,
+    pub focus_dist: f64,
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            look_from: Point3::new(13.0, 2.0, 3.0),
+            look_at: Point3::new(0.0, 0.0, 0.0),
+            vup: Vec3::new(0.0, 1.0, 0.0),
+            vfov: 20.0,
+            aspect_ratio: 3.0 / 2.0,
+            aperture: 0.1,
+            focus_dist: 10.0,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+        }
+    }
+}
+
+impl CameraSettings {
+    // check that the settings describe a usable camera
+    pub fn validate(&self) -> Result<(), String> {
+        if self.aspect_ratio <= 0.0 {
+            return Err("camera: aspect ratio must be positive".to_string());
+        }
+        if self.vfov <= 0.0 || self.vfov >= 180.0 {
+            return Err(
+                "camera: vertical field of view must be between 0 and 180 degrees".to_string(),
+            );
+        }
+        if self.aperture < 0.0 {
+            return Err("camera: aperture must not be negative".to_string());
+        }
+        if self.focus_dist <= 0.0 {
+            return Err("camera: focus distance must be positive".to_string());
+        }
+        if self.shutter_close < self.shutter_open {
+            return Err("camera: the shutter must not close before it opens".to_string());
+        }
+        let w = self.look_from - self.look_at;
+        if w.near_zero() {
+            return Err("camera: look_from and look_at must be different points".to_string());
+        }
+        if self.vup.cross(&w).near_zero() {
+            return Err("camera: vup must not be parallel to the viewing direction".to_string());
+        }
+        return Ok(());
+    }
+
+    pub fn build(&self) -> Camera {
+        Camera::new(
+            self.look_from,
+            self.look_at,
+            self.vup,
+            self.vfov,
+            self.aspect_ratio,
+            self.aperture,
+            self.focus_dist,
+        )
+        .with_shutter(self.shutter_open, self.shutter_close)
+    }
 }
 
 impl Camera {
@@ -50,22 +128,35 @@ impl Camera {
             lens_radius,
             u,
             v,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
         }
     }
+
+    // keep the shutter open from `open` until `close`, rather than only at time 0
+    pub fn with_shutter(mut self, open: f64, close: f64) -> Self {
+        self.shutter_open = open;
+        self.shutter_close = close;
+        return self;
+    }
 }
 
 impl Camera {
     // get a ray from the camera to the position (s, t) on the viewport, where `s` and `t`
-    // are normalised co-ordinates in [0, 1]
-    pub fn get_ray(&self, s: f64, t: f64, gen: &mut ThreadRng) -> Ray {
-        let rd = self.lens_radius * random_in_unit_disk(gen);
-        let offset = self.u * rd.x + self.v * rd.y;
+    // are normalised co-ordinates in [0, 1]. the point on the lens (a 2D sample) and the time
+    // (a 1D sample) are drawn from `sampler`.
+    pub fn get_ray(&self, s: f64, t: f64, sampler: &mut dyn Sampler) -> Ray {
+        let (lens_x, lens_y) = concentric_disk(sampler.get_2d());
+        let offset = self.lens_radius * (self.u * lens_x + self.v * lens_y);
 
         let dir: Vec3 =
             self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset;
+        let time = self.shutter_open + sampler.get_1d() * (self.shutter_close - self.shutter_open);
         Ray {
             origin: self.origin + offset,
             dir,
+            time,
+            wavelength: None,
         }
     }
 }
@@ -0,0 +1,343 @@
+use crate::camera::CameraSettings;
+use crate::film::Filter;
+use crate::image_io::ImageFormat;
+use crate::render::{AdaptiveSettings, RenderSettings};
+use crate::sampler::SamplerKind;
+use crate::tonemap::{ToneMapper, ToneMapping};
+use crate::vec3::{Point3, Vec3};
+use std::fmt;
+use std::path::PathBuf;
+
+const DEFAULT_SAMPLES_PER_PIXEL: u32 = 16;
+const DEFAULT_IMAGE_WIDTH: u32 = 900;
+const DEFAULT_MAX_DEPTH: u32 = 100;
+const DEFAULT_MAX_SAMPLES: u32 = 1024;
+const DEFAULT_OUTPUT_PATH: &str = "render.png";
+
+pub const USAGE: &str = "\
+usage: raytrace [options]
+
+Renders the scene to an image. Without --scene, a random scene is generated.
+
+options:
+  --scene <path>           scene file to render (see src/scene.rs for the format)
+  --output <path>          where to write the image, as .png, .ppm or .hdr [default: render.png]
+  --bit-depth <8|16>       bits per channel for PNG output [default: 8]
+  --exposure <stops>       brighten (or, if negative, darken) the PNG or PPM output by this
+                           many stops [default: 0]
+  --tone-map <name>        how bright colours are brought into range for PNG or PPM output:
+                           clamp, reinhard, aces or agx [default: clamp]
+  --white-balance <K>      the colour temperature, in kelvin, of light which should appear
+                           white in PNG or PPM output [default: 6504, i.e. no change]
+  --samples <n>            samples per pixel, or the least per pixel with --noise-threshold
+                           [default: 16]
+  --noise-threshold <x>    sample each pixel until the standard error of its brightness (on the
+                           0-1 scale of the output) falls below this, between --samples and
+                           --max-samples samples
+  --max-samples <n>        most samples per pixel with --noise-threshold [default: 1024]
+  --sample-map <path>      also write an image of the number of samples taken for each pixel
+  --width <n>              image width in pixels, at least 2 [default: 900]
+  --max-depth <n>          cap on the number of bounces per ray [default: 100]
+  --threads <n>            number of render threads [default: number of CPUs]
+  --seed <n>               seed for the random scene and sampling [default: 0]
+  --filter <name>          how samples are weighted into nearby pixels: box, tent, gaussian,
+                           mitchell or lanczos [default: box]
+  --filter-radius <x>      the filter's radius in pixels [default: 0.5 for box, 1 for tent, 1.5
+                           for gaussian, 2 for mitchell, 3 for lanczos]
+  --sampler <name>         how samples are spread over each pixel: independent, stratified,
+                           halton, sobol or bluenoise [default: sobol]
+  --spectral               trace wavelengths of light rather than red, green and blue, so that
+                           dispersive glass splits light into its colors
+
+camera options, which override the values from the scene file:
+  --aspect-ratio <x>       image width / height
+  --vfov <degrees>         vertical field of view
+  --aperture <x>           lens diameter, or 0 for a pinhole camera
+  --focus-dist <x>         distance from the camera to the plane of focus
+  --look-from <x,y,z>      camera position
+  --look-at <x,y,z>        point the camera faces
+  --vup <x,y,z>            camera 'up' direction
+  --shutter-open <t>       time at which the shutter opens, for motion blur
+  --shutter-close <t>      time at which the shutter closes
+
+  -h, --help               print this message
+";
+
+// settings for a single render, as given on the command line
+pub struct Settings {
+    pub scene_path: Option<PathBuf>,
+    pub output_path: PathBuf,
+    pub output_format: ImageFormat,
+    // where to write the map of samples taken per pixel, if anywhere
+    pub sample_map_path: Option<PathBuf>,
+    // how the render is brought into the range of the LDR output formats
+    pub tone_mapping: ToneMapping,
+    pub render: RenderSettings,
+    pub camera: CameraOverrides,
+}
+
+// camera parameters given on the command line, which take precedence over the scene file
+#[derive(Default)]
+pub struct CameraOverrides {
+    pub look_from: Option<Point3>,
+    pub look_at: Option<Point3>,
+    pub vup: Option<Vec3>,
+    pub vfov: Option<f64>,
+    pub aspect_ratio: Option<f64>,
+    pub aperture: Option<f64>,
+    pub focus_dist: Option<f64>,
+    pub shutter_open: Option<f64>,
+    pub shutter_close: Option<f64>,
+}
+
+impl CameraOverrides {
+    pub fn apply(&self, camera: &mut CameraSettings) {
+        camera.look_from = self.look_from.unwrap_or(camera.look_from);
+        camera.look_at = self.look_at.unwrap_or(camera.look_at);
+        camera.vup = self.vup.unwrap_or(camera.vup);
+        camera.vfov = self.vfov.unwrap_or(camera.vfov);
+        camera.aspect_ratio = self.aspect_ratio.unwrap_or(camera.aspect_ratio);
+        camera.aperture = self.aperture.unwrap_or(camera.aperture);
+        camera.focus_dist = self.focus_dist.unwrap_or(camera.focus_dist);
+        camera.shutter_open = self.shutter_open.unwrap_or(camera.shutter_open);
+        camera.shutter_close = self.shutter_close.unwrap_or(camera.shutter_close);
+    }
+}
+
+#[derive(Debug)]
+pub enum CliError {
+    // `--help` was requested
+    Help,
+    Invalid(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Help => write!(f, "help requested"),
+            CliError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+// parse the command line arguments (excluding the program name)
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Settings, CliError> {
+    let num_cpus = std::thread::available_parallelism().map_or(1, |n| n.get() as u32);
+    let mut settings = Settings {
+        scene_path: None,
+        output_path: PathBuf::from(DEFAULT_OUTPUT_PATH),
+        output_format: ImageFormat::Png8,
+        sample_map_path: None,
+        tone_mapping: ToneMapping::default(),
+        render: RenderSettings {
+            samples_per_pixel: DEFAULT_SAMPLES_PER_PIXEL,
+            image_width: DEFAULT_IMAGE_WIDTH,
+            max_depth: DEFAULT_MAX_DEPTH,
+            num_threads: num_cpus,
+            seed: 0,
+            sampler: SamplerKind::Sobol,
+            filter: Filter::from_name("box").unwrap(),
+            adaptive: None,
+            spectral: false,
+        },
+        camera: CameraOverrides::default(),
+    };
+
+    let mut bit_depth = 8;
+    let mut noise_threshold = None;
+    let mut max_samples = None;
+    let mut filter_radius = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            return Err(CliError::Help);
+        }
+
+        // accept both `--flag value` and `--flag=value`
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+        if !flag.starts_with("--") {
+            return Err(invalid(format!("unexpected argument `{}`", flag)));
+        }
+
+        // switches, which take no value
+        if flag == "--spectral" {
+            if inline_value.is_some() {
+                return Err(invalid(format!("`{}` doesn't take a value", flag)));
+            }
+            settings.render.spectral = true;
+            continue;
+        }
+        let value = match inline_value.or_else(|| args.next()) {
+            Some(value) => value,
+            None => return Err(invalid(format!("`{}` requires a value", flag))),
+        };
+
+        let r = &mut settings.render;
+        let c = &mut settings.camera;
+        match flag.as_str() {
+            "--scene" => settings.scene_path = Some(PathBuf::from(value)),
+            "--output" => settings.output_path = PathBuf::from(value),
+            "--bit-depth" => {
+                bit_depth = match value.as_str() {
+                    "8" => 8,
+                    "16" => 16,
+                    _ => return Err(invalid(format!("`{}` should be 8 or 16", flag))),
+                }
+            }
+            "--exposure" => settings.tone_mapping.exposure = float(&flag, &value)?,
+            "--tone-map" => {
+                settings.tone_mapping.tone_mapper = match ToneMapper::from_name(&value) {
+                    Some(tone_mapper) => tone_mapper,
+                    None => return Err(invalid(format!("unknown tone mapper `{}`", value))),
+                }
+            }
+            "--white-balance" => {
+                let temperature = float(&flag, &value)?;
+                if !(1667.0..=25000.0).contains(&temperature) {
+                    return Err(invalid(format!(
+                        "`{}` should be between 1667 and 25000",
+                        flag
+                    )));
+                }
+                settings.tone_mapping.white_balance = Some(temperature);
+            }
+            "--samples" => r.samples_per_pixel = positive_int(&flag, &value)?,
+            "--noise-threshold" => {
+                let threshold = float(&flag, &value)?;
+                if threshold <= 0.0 {
+                    return Err(invalid(format!("`{}` should be positive", flag)));
+                }
+                noise_threshold = Some(threshold);
+            }
+            "--max-samples" => max_samples = Some(positive_int(&flag, &value)?),
+            "--sample-map" => settings.sample_map_path = Some(PathBuf::from(value)),
+            "--width" => {
+                // pixels are placed across the view at `i / (width - 1)`, which needs two columns
+                let width = positive_int(&flag, &value)?;
+                if width < 2 {
+                    return Err(invalid(format!("`{}` should be at least 2", flag)));
+                }
+                r.image_width = width;
+            }
+            "--max-depth" => r.max_depth = positive_int(&flag, &value)?,
+            "--threads" => r.num_threads = positive_int(&flag, &value)?,
+            "--seed" => {
+                r.seed = match value.parse::<u64>() {
+                    Ok(seed) => seed,
+                    Err(_) => {
+                        return Err(invalid(format!(
+                            "`{}` should be a non-negative integer, found `{}`",
+                            flag, value
+                        )))
+                    }
+                }
+            }
+            "--filter" => {
+                r.filter = match Filter::from_name(&value) {
+                    Some(filter) => filter,
+                    None => return Err(invalid(format!("unknown filter `{}`", value))),
+                }
+            }
+            "--filter-radius" => {
+                let radius = float(&flag, &value)?;
+                if radius <= 0.0 {
+                    return Err(invalid(format!("`{}` should be positive", flag)));
+                }
+                filter_radius = Some(radius);
+            }
+            "--sampler" => {
+                r.sampler = match SamplerKind::from_name(&value) {
+                    Some(kind) => kind,
+                    None => return Err(invalid(format!("unknown sampler `{}`", value))),
+                }
+            }
+            "--aspect-ratio" => c.aspect_ratio = Some(float(&flag, &value)?),
+            "--vfov" => c.vfov = Some(float(&flag, &value)?),
+            "--aperture" => c.aperture = Some(float(&flag, &value)?),
+            "--focus-dist" => c.focus_dist = Some(float(&flag, &value)?),
+            "--shutter-open" => c.shutter_open = Some(float(&flag, &value)?),
+            "--shutter-close" => c.shutter_close = Some(float(&flag, &value)?),
+            "--look-from" => c.look_from = Some(vector(&flag, &value)?),
+            "--look-at" => c.look_at = Some(vector(&flag, &value)?),
+            "--vup" => c.vup = Some(vector(&flag, &value)?),
+            _ => return Err(invalid(format!("unknown option `{}`", flag))),
+        }
+    }
+
+    settings.output_format =
+        ImageFormat::from_path(&settings.output_path, bit_depth).map_err(invalid)?;
+    if let Some(path) = &settings.sample_map_path {
+        ImageFormat::from_path(path, 8).map_err(invalid)?;
+    }
+
+    if let Some(radius) = filter_radius {
+        settings.render.filter.radius = radius;
+    }
+
+    settings.render.adaptive = match (noise_threshold, max_samples) {
+        (Some(noise_threshold), max_samples) => {
+            let max_samples = max_samples.unwrap_or(DEFAULT_MAX_SAMPLES);
+            if max_samples < settings.render.samples_per_pixel {
+                return Err(invalid(
+                    "`--max-samples` should be at least `--samples`".to_string(),
+                ));
+            }
+            Some(AdaptiveSettings {
+                noise_threshold,
+                max_samples,
+                exposure: settings.tone_mapping.exposure,
+            })
+        }
+        (None, Some(_)) => {
+            return Err(invalid(
+                "`--max-samples` only applies with `--noise-threshold`".to_string(),
+            ))
+        }
+        (None, None) => None,
+    };
+    return Ok(settings);
+}
+
+fn invalid(message: String) -> CliError {
+    CliError::Invalid(message)
+}
+
+fn positive_int(flag: &str, value: &str) -> Result<u32, CliError> {
+    match value.parse::<u32>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(invalid(format!(
+            "`{}` should be a positive integer, found `{}`",
+            flag, value
+        ))),
+    }
+}
+
+fn float(flag: &str, value: &str) -> Result<f64, CliError> {
+    match value.parse::<f64>() {
+        Ok(x) if x.is_finite() => Ok(x),
+        _ => Err(invalid(format!(
+            "`{}` should be a number, found `{}`",
+            flag, value
+        ))),
+    }
+}
+
+fn vector(flag: &str, value: &str) -> Result<Vec3, CliError> {
+    let parts: Vec<&str> = value.split(',').collect();
+    if parts.len() != 3 {
+        return Err(invalid(format!(
+            "`{}` should be a vector `x,y,z`, found `{}`",
+            flag, value
+        )));
+    }
+    return Ok(Vec3::new(
+        float(flag, parts[0])?,
+        float(flag, parts[1])?,
+        float(flag, parts[2])?,
+    ));
+}
@@ -0,0 +1,193 @@
+use crate::hit_record::HitRecord;
+use crate::material::{Material, ScatterRecord};
+use crate::microfacet::{fresnel_dielectric, reflect, Ggx};
+use crate::onb::Onb;
+use crate::ray::Ray;
+use crate::texture::Texture;
+use crate::vec3::{Color, Vec3};
+use rand::{Rng, RngCore};
+use std::sync::Arc;
+
+// a clear coat, such as varnish or the lacquer on car paint, over any other material. the coat
+// is a thin dielectric layer which reflects light in proportion to its Fresnel reflectance,
+// either as a mirror or, when `roughness` is above 0, as a GGX distribution of microfacets.
+//
+// the light which gets through the coat is shaded by `base`, and then has to pass back out
+// through the coat again, so the base is weighted by the coat's transmittance in both directions
+// (1 minus its reflectance at the surface normal). the layers therefore never reflect more light
+// between them than arrives. light reflected back down by the inside of the coat is ignored.
+pub struct Coated {
+    pub base: Arc<dyn Material + Send + Sync>,
+    // the index of refraction of the coat
+    pub ior: f64,
+    // the perceptual roughness of the coat in [0, 1], where 0 is perfectly smooth
+    pub roughness: f64,
+    // the fraction of each color which passes through the coat straight down to the base and
+    // back up again. the path through the coat is longer at an angle, and tints the base more.
+    pub tint: Arc<dyn Texture>,
+}
+
+impl Coated {
+    // the smallest chance of sampling the coat rather than the base, as its reflectance (and
+    // so the share of light it reflects) can be very small, while its highlights are bright
+    const MIN_COAT_PROBABILITY: f64 = 0.25;
+
+    fn reflectance(&self, cos: f64) -> f64 {
+        fresnel_dielectric(cos, self.ior)
+    }
+
+    // the chance of sampling the coat rather than the base, seen from `wo`
+    fn coat_probability(&self, wo: Vec3) -> f64 {
+        self.reflectance(wo.z).max(Self::MIN_COAT_PROBABILITY)
+    }
+
+    // the fraction of light which crosses the coat on its way from `wi` down to the base and
+    // then back up towards `wo`
+    fn transmittance(&self, tint: Color, wo: Vec3, wi: Vec3) -> Color {
+        let (cos_o, cos_i) = (wo.z.abs(), wi.z.abs());
+        let transmitted = (1.0 - self.reflectance(cos_o)) * (1.0 - self.reflectance(cos_i));
+        // the distance travelled through the coat, relative to that at normal incidence, along
+        // the refracted directions
+        let refracted_cos = |cos: f64| f64::sqrt(1.0 - (1.0 - cos * cos) / (self.ior * self.ior));
+        let distance = 0.5 * (1.0 / refracted_cos(cos_o) + 1.0 / refracted_cos(cos_i));
+        return transmitted
+            * Color::new(
+                tint.x.powf(distance),
+                tint.y.powf(distance),
+                tint.z.powf(distance),
+            );
+    }
+
+    fn eval_coat(&self, ggx: &Ggx, wo: Vec3, wi: Vec3) -> f64 {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+        let wm = (wo + wi).unit_vector();
+        let fresnel = self.reflectance(wo.dot(&wm));
+        return ggx.d(wm) * ggx.g(wo, wi) * fresnel / (4.0 * wo.z);
+    }
+
+    fn pdf_coat(&self, ggx: &Ggx, wo: Vec3, wi: Vec3) -> f64 {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+        let wm = (wo + wi).unit_vector();
+        return ggx.visible_d(wo, wm) / (4.0 * wo.dot(&wm));
+    }
+}
+
+impl Material for Coated {
+    // a smooth coat over a rough base, or a rough coat over a mirror-like one
+    fn has_mixed_lobes(&self) -> bool {
+        true
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.base.is_dispersive()
+    }
+
+    fn scatter(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        gen: &mut dyn RngCore,
+    ) -> Option<ScatterRecord> {
+        let onb = Onb::from_w(record.normal);
+        let wo = onb.to_local(-ray.dir.unit_vector());
+        if wo.z <= 0.0 {
+            return None;
+        }
+        let p = self.coat_probability(wo);
+        let is_smooth = self.roughness <= 0.0;
+
+        let dir = if gen.gen::<f64>() < p {
+            if is_smooth {
+                return Some(ScatterRecord {
+                    ray: Ray {
+                        origin: record.p,
+                        dir: onb.to_world(Vec3::new(-wo.x, -wo.y, wo.z)),
+                        time: ray.time,
+                        wavelength: ray.wavelength,
+                    },
+                    attenuation: Color::new(1.0, 1.0, 1.0) * self.reflectance(wo.z) / p,
+                    pdf: None,
+                    dispersive: false,
+                });
+            }
+            let ggx = Ggx::from_roughness(self.roughness);
+            let wi = reflect(wo, ggx.sample_visible_normal(wo, (gen.gen(), gen.gen())));
+            if wi.z <= 0.0 {
+                return None;
+            }
+            onb.to_world(wi)
+        } else {
+            let scatter = self.base.scatter(ray, record, gen)?;
+            let tint = self.tint.value(record.u, record.v, record.p);
+            let wi = onb.to_local(scatter.ray.dir.unit_vector());
+            let transmittance = self.transmittance(tint, wo, wi);
+            // a mirror-like coat adds nothing in the base's direction, and a mirror-like base
+            // can't be weighed against the coat, so either way only the base's share counts
+            match scatter.pdf {
+                Some(_) if !is_smooth => scatter.ray.dir,
+                pdf => {
+                    return Some(ScatterRecord {
+                        ray: scatter.ray,
+                        attenuation: transmittance * scatter.attenuation / (1.0 - p),
+                        pdf: pdf.map(|pdf| (1.0 - p) * pdf),
+                        dispersive: scatter.dispersive,
+                    })
+                }
+            }
+        };
+
+        // a rough coat, weighed against the base in the chosen direction
+        let pdf = self.pdf(ray, record, dir);
+        if pdf <= 0.0 {
+            return None;
+        }
+        return Some(ScatterRecord {
+            ray: Ray {
+                origin: record.p,
+                dir,
+                time: ray.time,
+                wavelength: ray.wavelength,
+            },
+            attenuation: self.eval(ray, record, dir) / pdf,
+            pdf: Some(pdf),
+            // the base's share is evaluated for the ray's wavelength alone
+            dispersive: self.base.is_dispersive(),
+        });
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, dir: Vec3) -> Color {
+        let onb = Onb::from_w(record.normal);
+        let wo = onb.to_local(-ray.dir.unit_vector());
+        let wi = onb.to_local(dir.unit_vector());
+        if wo.z <= 0.0 {
+            return Color::zeroes();
+        }
+        let tint = self.tint.value(record.u, record.v, record.p);
+        let base = self.transmittance(tint, wo, wi) * self.base.eval(ray, record, dir);
+        if self.roughness <= 0.0 {
+            return base;
+        }
+        let coat = self.eval_coat(&Ggx::from_roughness(self.roughness), wo, wi);
+        return base + Color::new(1.0, 1.0, 1.0) * coat;
+    }
+
+    fn pdf(&self, ray: &Ray, record: &HitRecord, dir: Vec3) -> f64 {
+        let onb = Onb::from_w(record.normal);
+        let wo = onb.to_local(-ray.dir.unit_vector());
+        let wi = onb.to_local(dir.unit_vector());
+        if wo.z <= 0.0 {
+            return 0.0;
+        }
+        let p = self.coat_probability(wo);
+        let base = (1.0 - p) * self.base.pdf(ray, record, dir);
+        if self.roughness <= 0.0 {
+            return base;
+        }
+        let coat = self.pdf_coat(&Ggx::from_roughness(self.roughness), wo, wi);
+        return base + p * coat;
+    }
+}
@@ -0,0 +1,127 @@
+// colour spaces and conversions between them. colours in the renderer are linear sRGB (that is,
+// Rec. 709 primaries with a D65 white point), unless said otherwise.
+
+use crate::vec3::Color;
+
+// a 3x3 matrix, in row-major order, for linear transforms between colour spaces
+pub type Mat3 = [[f64; 3]; 3];
+
+pub const SRGB_TO_XYZ: Mat3 = [
+    [0.4124564, 0.3575761, 0.1804375],
+    [0.2126729, 0.7151522, 0.0721750],
+    [0.0193339, 0.1191920, 0.9503041],
+];
+
+pub const XYZ_TO_SRGB: Mat3 = [
+    [3.2404542, -1.5371385, -0.4985314],
+    [-0.9692660, 1.8760108, 0.0415560],
+    [0.0556434, -0.2040259, 1.0572252],
+];
+
+// from XYZ to the cone-like responses of the Bradford chromatic adaptation transform
+const XYZ_TO_BRADFORD: Mat3 = [
+    [0.8951, 0.2664, -0.1614],
+    [-0.7502, 1.7135, 0.0367],
+    [0.0389, -0.0685, 1.0296],
+];
+
+const BRADFORD_TO_XYZ: Mat3 = [
+    [0.9869929, -0.1470543, 0.1599627],
+    [0.4323053, 0.5183603, 0.0492912],
+    [-0.0085287, 0.0400428, 0.9684867],
+];
+
+pub fn transform(m: &Mat3, c: Color) -> Color {
+    Color::new(
+        m[0][0] * c.x + m[0][1] * c.y + m[0][2] * c.z,
+        m[1][0] * c.x + m[1][1] * c.y + m[1][2] * c.z,
+        m[2][0] * c.x + m[2][1] * c.y + m[2][2] * c.z,
+    )
+}
+
+// the matrix which applies `b` and then `a`
+pub fn multiply(a: &Mat3, b: &Mat3) -> Mat3 {
+    let mut m = [[0.0; 3]; 3];
+    for (i, row) in m.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    return m;
+}
+
+// the relative luminance (the Y of XYZ) of a linear sRGB colour
+pub fn luminance(c: Color) -> f64 {
+    0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z
+}
+
+// the sRGB opto-electronic transfer function: from a linear value in [0, 1] to the encoded
+// value stored in an image file
+pub fn srgb_oetf(x: f64) -> f64 {
+    if x <= 0.0031308 {
+        return 12.92 * x;
+    }
+    return 1.055 * x.powf(1.0 / 2.4) - 0.055;
+}
+
+// the inverse of `srgb_oetf`, from an encoded value in [0, 1] back to linear
+pub fn srgb_eotf(x: f64) -> f64 {
+    if x <= 0.04045 {
+        return x / 12.92;
+    }
+    return ((x + 0.055) / 1.055).powf(2.4);
+}
+
+// the chromaticity (x, y) of a black body at `temperature` kelvin, from the cubic spline fit of
+// Kim et al., which holds between 1667 K and 25000 K
+pub fn black_body_chromaticity(temperature: f64) -> (f64, f64) {
+    let t = temperature.clamp(1667.0, 25000.0);
+    let (t2, t3) = (t * t, t * t * t);
+    let x = match t <= 4000.0 {
+        true => -0.2661239e9 / t3 - 0.2343589e6 / t2 + 0.8776956e3 / t + 0.179910,
+        false => -3.0258469e9 / t3 + 2.1070379e6 / t2 + 0.2226347e3 / t + 0.240390,
+    };
+    let (x2, x3) = (x * x, x * x * x);
+    let y = if t <= 2222.0 {
+        -1.1063814 * x3 - 1.34811020 * x2 + 2.18555832 * x - 0.20219683
+    } else if t <= 4000.0 {
+        -0.9549476 * x3 - 1.37418593 * x2 + 2.09137015 * x - 0.16748867
+    } else {
+        3.0817580 * x3 - 5.87338670 * x2 + 3.75112997 * x - 0.37001483
+    };
+    return (x, y);
+}
+
+// the XYZ colour, with a luminance of 1, of the chromaticity (x, y)
+fn chromaticity_to_xyz((x, y): (f64, f64)) -> Color {
+    Color::new(x / y, 1.0, (1.0 - x - y) / y)
+}
+
+// the linear sRGB transform which maps the white `from` to the white `to` (both chromaticities),
+// using the Bradford transform: each of the cone-like responses is scaled independently
+pub fn chromatic_adaptation(from: (f64, f64), to: (f64, f64)) -> Mat3 {
+    let source = transform(&XYZ_TO_BRADFORD, chromaticity_to_xyz(from));
+    let target = transform(&XYZ_TO_BRADFORD, chromaticity_to_xyz(to));
+    let scale = [
+        [target.x / source.x, 0.0, 0.0],
+        [0.0, target.y / source.y, 0.0],
+        [0.0, 0.0, target.z / source.z],
+    ];
+    let adapt = multiply(&BRADFORD_TO_XYZ, &multiply(&scale, &XYZ_TO_BRADFORD));
+    return multiply(&XYZ_TO_SRGB, &multiply(&adapt, &SRGB_TO_XYZ));
+}
+
+// the CIE 1931 colour matching functions at `wavelength` nanometres, from the multi-lobe fit of
+// Wyman, Sloan and Shirley, "Simple Analytic Approximations to the CIE XYZ Color Matching
+// Functions" (2013)
+pub fn cie_xyz(wavelength: f64) -> Color {
+    let g = |mu: f64, sigma1: f64, sigma2: f64| {
+        let t = (wavelength - mu) / if wavelength < mu { sigma1 } else { sigma2 };
+        f64::exp(-0.5 * t * t)
+    };
+    let x =
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2);
+    let y = 0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1);
+    let z = 1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8);
+    return Color::new(x, y, z);
+}
@@ -0,0 +1,97 @@
+use crate::hit_record::HitRecord;
+use crate::material::{Material, ScatterRecord};
+use crate::microfacet::{fresnel_conductor, reflect, Ggx};
+use crate::onb::Onb;
+use crate::ray::Ray;
+use crate::vec3::{Color, Vec3};
+use rand::{Rng, RngCore};
+
+// a rough metal, described by its complex index of refraction `eta` + i `k` in each of the red,
+// green and blue channels. the surface is a GGX distribution of perfectly reflecting
+// microfacets, and its color comes entirely from the exact Fresnel reflectance.
+pub struct Conductor {
+    pub eta: Color,
+    pub k: Color,
+    pub roughness: f64,
+}
+
+// the index of refraction (`eta`, `k`) of some common metals, at wavelengths of roughly 650,
+// 550 and 450 nm
+pub fn metal_ior(name: &str) -> Option<(Color, Color)> {
+    let (eta, k) = match name {
+        "gold" => ((0.143, 0.374, 1.442), (3.983, 2.385, 1.603)),
+        "silver" => ((0.155, 0.117, 0.138), (4.828, 3.122, 2.147)),
+        "copper" => ((0.200, 0.924, 1.102), (3.912, 2.452, 2.142)),
+        "aluminium" => ((1.657, 0.880, 0.521), (9.224, 6.270, 4.837)),
+        _ => return None,
+    };
+    return Some((Color::new(eta.0, eta.1, eta.2), Color::new(k.0, k.1, k.2)));
+}
+
+impl Conductor {
+    fn eval_local(&self, ggx: &Ggx, wo: Vec3, wi: Vec3) -> Color {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return Color::zeroes();
+        }
+        let wm = (wo + wi).unit_vector();
+        let fresnel = fresnel_conductor(wo.dot(&wm), self.eta, self.k);
+        return ggx.d(wm) * ggx.g(wo, wi) / (4.0 * wo.z) * fresnel;
+    }
+
+    fn pdf_local(&self, ggx: &Ggx, wo: Vec3, wi: Vec3) -> f64 {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+        let wm = (wo + wi).unit_vector();
+        return ggx.visible_d(wo, wm) / (4.0 * wo.dot(&wm));
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        gen: &mut dyn RngCore,
+    ) -> Option<ScatterRecord> {
+        let ggx = Ggx::from_roughness(self.roughness);
+        let onb = Onb::from_w(record.normal);
+        let wo = onb.to_local(-ray.dir.unit_vector());
+        if wo.z <= 0.0 {
+            return None;
+        }
+        let wm = ggx.sample_visible_normal(wo, (gen.gen(), gen.gen()));
+        let wi = reflect(wo, wm);
+        if wi.z <= 0.0 {
+            return None;
+        }
+
+        // with visible normals sampled, all but the Fresnel and the shadowing of `wi` cancels
+        let fresnel = fresnel_conductor(wo.dot(&wm), self.eta, self.k);
+        return Some(ScatterRecord {
+            ray: Ray {
+                origin: record.p,
+                dir: onb.to_world(wi),
+                time: ray.time,
+                wavelength: ray.wavelength,
+            },
+            attenuation: ggx.g(wo, wi) / ggx.g1(wo) * fresnel,
+            pdf: Some(self.pdf_local(&ggx, wo, wi)),
+            dispersive: false,
+        });
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, dir: Vec3) -> Color {
+        let onb = Onb::from_w(record.normal);
+        let wo = onb.to_local(-ray.dir.unit_vector());
+        let wi = onb.to_local(dir.unit_vector());
+        return self.eval_local(&Ggx::from_roughness(self.roughness), wo, wi);
+    }
+
+    fn pdf(&self, ray: &Ray, record: &HitRecord, dir: Vec3) -> f64 {
+        let onb = Onb::from_w(record.normal);
+        let wo = onb.to_local(-ray.dir.unit_vector());
+        let wi = onb.to_local(dir.unit_vector());
+        return self.pdf_local(&Ggx::from_roughness(self.roughness), wo, wi);
+    }
+}
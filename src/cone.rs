@@ -0,0 +1,98 @@
+use crate::aabb::Aabb;
+use crate::disk::circle_bounding_box;
+use crate::hit_record::HitRecord;
+use crate::hittable::Hittable;
+use crate::material::Material;
+use crate::onb::Onb;
+use crate::ray::Ray;
+use crate::util::solve_quadratic;
+use crate::vec3::{Point3, Vec3};
+use rand::RngCore;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+// a finite cone with a circular base of `radius` around `base`, narrowing to a point at `apex`,
+// optionally closed off by a disk at the base. the front face is the outside.
+pub struct Cone {
+    pub base: Point3,
+    pub apex: Point3,
+    pub radius: f64,
+    pub capped: bool,
+    pub mat_ptr: Arc<dyn Material + Send + Sync>,
+}
+
+impl Hittable for Cone {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        record: &mut HitRecord,
+        _gen: &mut dyn RngCore,
+    ) -> bool {
+        // work in a frame where the cone runs up the z axis, from its base at the origin to its
+        // apex at z = h. the radius at height z is k (h - z).
+        let axis = self.apex - self.base;
+        let h = axis.length();
+        let basis = Onb::from_w(axis);
+        let o = basis.to_local(ray.origin - self.base);
+        let d = basis.to_local(ray.dir);
+        let k = self.radius / h;
+        let k2 = k * k;
+
+        // the closest hit so far, as (t, local outward normal, u, v)
+        let mut closest: Option<(f64, Vec3, f64, f64)> = None;
+        let mut t_max = t_max;
+
+        // the sloped side, where x^2 + y^2 = k^2 (h - z)^2
+        let a = d.x * d.x + d.y * d.y - k2 * d.z * d.z;
+        let b = 2.0 * (o.x * d.x + o.y * d.y + k2 * (h - o.z) * d.z);
+        let c = o.x * o.x + o.y * o.y - k2 * (h - o.z) * (h - o.z);
+        if let Some((t0, t1)) = solve_quadratic(a, b, c) {
+            for t in [t0, t1] {
+                let p = o + t * d;
+                if t < t_min || t_max < t || p.z < 0.0 || p.z > h {
+                    continue;
+                }
+                // the gradient of the implicit surface points outwards
+                let normal = Vec3::new(p.x, p.y, k2 * (h - p.z));
+                if normal.near_zero() {
+                    continue; // exactly at the apex
+                }
+                let u = (f64::atan2(p.y, p.x) + PI) / (2.0 * PI);
+                closest = Some((t, normal.unit_vector(), u, p.z / h));
+                t_max = t;
+                break;
+            }
+        }
+
+        // the flat base
+        if self.capped && d.z.abs() > 1e-12 {
+            let t = -o.z / d.z;
+            let p = o + t * d;
+            let r2 = p.x * p.x + p.y * p.y;
+            if t >= t_min && t <= t_max && r2 <= self.radius * self.radius {
+                let u = (f64::atan2(p.y, p.x) + PI) / (2.0 * PI);
+                closest = Some((t, Vec3::new(0.0, 0.0, -1.0), u, r2.sqrt() / self.radius));
+            }
+        }
+
+        let (t, local_normal, u, v) = match closest {
+            Some(hit) => hit,
+            None => return false,
+        };
+        record.t = t;
+        record.p = ray.at(t);
+        record.u = u;
+        record.v = v;
+        record.mat_ptr = Some(Arc::clone(&self.mat_ptr));
+        record.set_face_normal(ray, basis.to_world(local_normal));
+        return true;
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let normal = (self.apex - self.base).unit_vector();
+        let base = circle_bounding_box(self.base, normal, self.radius);
+        Some(base.include_point(self.apex))
+    }
+}
@@ -0,0 +1,72 @@
+use crate::aabb::Aabb;
+use crate::hit_record::HitRecord;
+use crate::hittable::Hittable;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+use rand::{Rng, RngCore};
+use std::sync::Arc;
+
+// a volume of fog or smoke of even density, filling the inside of `boundary`. a ray passing
+// through it travels a random distance before scattering, with longer paths through denser
+// media more likely to scatter. the boundary must be convex, since only the first stretch of
+// each ray inside it is considered.
+pub struct ConstantMedium {
+    pub boundary: Arc<dyn Hittable>,
+    pub density: f64,
+    pub phase_function: Arc<dyn Material + Send + Sync>,
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        record: &mut HitRecord,
+        gen: &mut dyn RngCore,
+    ) -> bool {
+        // find where the ray enters and leaves the boundary, whether or not those points are
+        // within [t_min, t_max], since the ray may start inside the volume
+        let mut entry = HitRecord::dummy();
+        let mut exit = HitRecord::dummy();
+        if !self
+            .boundary
+            .hit(ray, f64::NEG_INFINITY, f64::INFINITY, &mut entry, gen)
+        {
+            return false;
+        }
+        if !self
+            .boundary
+            .hit(ray, entry.t + 0.0001, f64::INFINITY, &mut exit, gen)
+        {
+            return false;
+        }
+
+        let t0 = f64::max(entry.t, t_min);
+        let t1 = f64::min(exit.t, t_max);
+        if t0 >= t1 {
+            return false;
+        }
+
+        // the free-flight distance is exponentially distributed
+        let ray_length = ray.dir.length();
+        let distance_inside = (t1 - t0) * ray_length;
+        let hit_distance = -(1.0 - gen.gen::<f64>()).ln() / self.density;
+        if hit_distance > distance_inside {
+            return false;
+        }
+
+        record.t = t0 + hit_distance / ray_length;
+        record.p = ray.at(record.t);
+        // a point inside the volume has no surface, so the normal and face are arbitrary
+        record.normal = Vec3::new(1.0, 0.0, 0.0);
+        record.front_face = true;
+        record.mat_ptr = Some(Arc::clone(&self.phase_function));
+        return true;
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.boundary.bounding_box()
+    }
+}
@@ -0,0 +1,96 @@
+use crate::aabb::Aabb;
+use crate::disk::circle_bounding_box;
+use crate::hit_record::HitRecord;
+use crate::hittable::Hittable;
+use crate::material::Material;
+use crate::onb::Onb;
+use crate::ray::Ray;
+use crate::util::solve_quadratic;
+use crate::vec3::{Point3, Vec3};
+use rand::RngCore;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+// a finite cylinder around the segment from `base` to `top`, optionally closed off by disks at
+// either end. the front face is the outside.
+pub struct Cylinder {
+    pub base: Point3,
+    pub top: Point3,
+    pub radius: f64,
+    pub capped: bool,
+    pub mat_ptr: Arc<dyn Material + Send + Sync>,
+}
+
+impl Hittable for Cylinder {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        record: &mut HitRecord,
+        _gen: &mut dyn RngCore,
+    ) -> bool {
+        // work in a frame where the cylinder runs up the z axis from the origin
+        let axis = self.top - self.base;
+        let height = axis.length();
+        let basis = Onb::from_w(axis);
+        let o = basis.to_local(ray.origin - self.base);
+        let d = basis.to_local(ray.dir);
+        let r = self.radius;
+
+        // the closest hit so far, as (t, local outward normal, u, v)
+        let mut closest: Option<(f64, Vec3, f64, f64)> = None;
+        let mut t_max = t_max;
+
+        // the curved side, where x^2 + y^2 = r^2
+        let a = d.x * d.x + d.y * d.y;
+        let b = 2.0 * (o.x * d.x + o.y * d.y);
+        let c = o.x * o.x + o.y * o.y - r * r;
+        if let Some((t0, t1)) = solve_quadratic(a, b, c) {
+            for t in [t0, t1] {
+                let p = o + t * d;
+                if t < t_min || t_max < t || p.z < 0.0 || p.z > height {
+                    continue;
+                }
+                let u = (f64::atan2(p.y, p.x) + PI) / (2.0 * PI);
+                closest = Some((t, Vec3::new(p.x / r, p.y / r, 0.0), u, p.z / height));
+                t_max = t;
+                break;
+            }
+        }
+
+        // the flat ends
+        if self.capped && d.z.abs() > 1e-12 {
+            for (z, normal_z) in [(0.0, -1.0), (height, 1.0)] {
+                let t = (z - o.z) / d.z;
+                let p = o + t * d;
+                if t < t_min || t_max < t || p.x * p.x + p.y * p.y > r * r {
+                    continue;
+                }
+                let u = (f64::atan2(p.y, p.x) + PI) / (2.0 * PI);
+                let v = f64::sqrt(p.x * p.x + p.y * p.y) / r;
+                closest = Some((t, Vec3::new(0.0, 0.0, normal_z), u, v));
+                t_max = t;
+            }
+        }
+
+        let (t, local_normal, u, v) = match closest {
+            Some(hit) => hit,
+            None => return false,
+        };
+        record.t = t;
+        record.p = ray.at(t);
+        record.u = u;
+        record.v = v;
+        record.mat_ptr = Some(Arc::clone(&self.mat_ptr));
+        record.set_face_normal(ray, basis.to_world(local_normal));
+        return true;
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let normal = (self.top - self.base).unit_vector();
+        let bottom = circle_bounding_box(self.base, normal, self.radius);
+        let top = circle_bounding_box(self.top, normal, self.radius);
+        Some(bottom.surrounding_box(&top))
+    }
+}
@@ -1,56 +1,326 @@
 use crate::hit_record::HitRecord;
-use crate::material::Material;
-use crate::metal::reflect;
+use crate::material::{Material, ScatterRecord};
+use crate::microfacet::{fresnel_dielectric, reflect, refract, Ggx};
+use crate::onb::Onb;
 use crate::ray::Ray;
+use crate::spectrum::{MAX_WAVELENGTH, MIN_WAVELENGTH};
 use crate::vec3::{Color, Vec3};
-use rand::prelude::ThreadRng;
-use rand::Rng;
+use rand::{Rng, RngCore};
 use std::f64;
 
-fn refract(unit_direction: Vec3, normal: Vec3, eta_on_eta_prime: f64, cos_theta: f64) -> Vec3 {
-    let r_out_orth = eta_on_eta_prime * (unit_direction + cos_theta * normal);
-    let r_out_par = -f64::sqrt(f64::abs(1.0 - r_out_orth.length_squared())) * normal;
-    return r_out_orth + r_out_par;
+// how a material's index of refraction varies with wavelength, which is given in nanometres
+// (although both formulas are written, as usual, for wavelengths in micrometres)
+pub enum Dispersion {
+    // n = a + b / wavelength^2
+    Cauchy { a: f64, b: f64 },
+    // n^2 = 1 + sum of b_i wavelength^2 / (wavelength^2 - c_i)
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
 }
 
+impl Dispersion {
+    // the wavelength of the sodium D line, at which indices of refraction are usually quoted
+    pub const REFERENCE_WAVELENGTH: f64 = 589.3;
+
+    // the Sellmeier coefficients of some common transparent materials
+    pub fn named(name: &str) -> Option<Self> {
+        let (b, c) = match name {
+            // borosilicate crown glass, the usual optical glass
+            "bk7" => (
+                [1.03961212, 0.231792344, 1.01046945],
+                [0.00600069867, 0.0200179144, 103.560653],
+            ),
+            // a dense flint glass, which spreads colors out far more
+            "sf11" => (
+                [1.73759695, 0.313747346, 1.89878101],
+                [0.013188707, 0.0623068142, 155.23629],
+            ),
+            "fused_silica" => (
+                [0.6961663, 0.4079426, 0.8974794],
+                [0.00467914826, 0.0135120631, 97.9340025],
+            ),
+            "diamond" => ([0.3306, 4.3356, 0.0], [0.030625, 0.011236, 0.0]),
+            _ => return None,
+        };
+        return Some(Dispersion::Sellmeier { b, c });
+    }
+
+    // whether the index of refraction is finite and at least 1 at every wavelength a path can
+    // carry. a Sellmeier term whose resonance (where the index goes to infinity) lies among
+    // them is ruled out directly, as sampling could step over it.
+    pub fn is_physical(&self) -> bool {
+        if let Dispersion::Sellmeier { b, c } = self {
+            let range = (MIN_WAVELENGTH / 1000.0).powi(2)..=(MAX_WAVELENGTH / 1000.0).powi(2);
+            if b.iter().zip(c).any(|(b, c)| *b != 0.0 && range.contains(c)) {
+                return false;
+            }
+        }
+        let steps = (MAX_WAVELENGTH - MIN_WAVELENGTH) as usize;
+        return (0..=steps).all(|i| {
+            let ir = self.ir(MIN_WAVELENGTH + i as f64);
+            ir.is_finite() && ir >= 1.0
+        });
+    }
+
+    pub fn ir(&self, wavelength: f64) -> f64 {
+        let l2 = (wavelength / 1000.0).powi(2);
+        match self {
+            Dispersion::Cauchy { a, b } => a + b / l2,
+            Dispersion::Sellmeier { b, c } => {
+                let sum: f64 = (0..3).map(|i| b[i] * l2 / (l2 - c[i])).sum();
+                f64::sqrt(1.0 + sum)
+            }
+        }
+    }
+}
+
+// a transparent solid such as glass or water, which reflects and refracts light in proportion
+// to the Fresnel reflectance at its surface.
+//
+// a rough surface is a GGX distribution of microfacets, each of which reflects or refracts,
+// following Walter et al., "Microfacet Models for Refraction through Rough Surfaces" (2007).
+// as light leaving a closed object passes out through as much surface as went in, the change in
+// radiance across each boundary (by a factor of eta squared) cancels out, and is left out.
+//
+// with a `dispersion`, each wavelength is bent by a different amount, splitting white light into
+// its colors, but only when rendering spectrally. otherwise `ir` is used for every color.
 pub struct Dielectric {
-    pub ir: f64, // index of refraction
+    // index of refraction
+    pub ir: f64,
+    pub dispersion: Option<Dispersion>,
+    // the index of refraction of the medium around the object, for instance 1.33 for glass
+    // under water, which must be modelled as a separate object surrounding the glass
+    pub exterior_ir: f64,
+    // the perceptual roughness in [0, 1], where 0 is perfectly smooth
+    pub roughness: f64,
+    // the fraction of light absorbed per unit distance travelled inside the object, in each
+    // channel (Beer-Lambert's law)
+    pub absorption: Color,
 }
 
-impl Material for Dielectric {
-    // todo: convince yourself this derivation is correct...
-    fn scatter(&self, ray: &Ray, record: &HitRecord, gen: &mut ThreadRng) -> Option<(Ray, Color)> {
-        let attenuation = Color::new(1.0, 1.0, 1.0);
-        let refraction_ratio = match record.front_face {
-            true => 1.0 / self.ir, // note: assumes `n` = 1.0 for air
-            false => self.ir,
+impl Dielectric {
+    // smooth, clear glass (or whatever `ir` makes it) in air
+    pub fn new(ir: f64) -> Self {
+        Self {
+            ir,
+            dispersion: None,
+            exterior_ir: 1.0,
+            roughness: 0.0,
+            absorption: Color::zeroes(),
+        }
+    }
+
+    // the index of refraction on the far side of the surface divided by that on the near side,
+    // for the wavelength carried by `ray`
+    fn eta(&self, ray: &Ray, record: &HitRecord) -> f64 {
+        let ir = match (&self.dispersion, ray.wavelength) {
+            (Some(dispersion), Some(wavelength)) => dispersion.ir(wavelength),
+            _ => self.ir,
+        };
+        match record.front_face {
+            true => ir / self.exterior_ir,
+            false => self.exterior_ir / ir,
+        }
+    }
+
+    // the fraction of light which survives the journey to the hit point. only light which hits
+    // the inside of the surface has travelled through the object.
+    fn transmittance(&self, ray: &Ray, record: &HitRecord) -> Color {
+        if record.front_face {
+            return Color::new(1.0, 1.0, 1.0);
+        }
+        let distance = record.t * ray.dir.length();
+        let a = self.absorption;
+        return Color::new(
+            f64::exp(-a.x * distance),
+            f64::exp(-a.y * distance),
+            f64::exp(-a.z * distance),
+        );
+    }
+
+    fn scatter_smooth(&self, ray: &Ray, record: &HitRecord, gen: &mut dyn RngCore) -> Vec3 {
+        let wo = -ray.dir.unit_vector();
+        // the shading normal of a smooth mesh can lean far enough to face away from the ray, in
+        // which case the boundary is seen from its other side
+        let (normal, eta) = match wo.dot(&record.normal) < 0.0 {
+            true => (-record.normal, 1.0 / self.eta(ray, record)),
+            false => (record.normal, self.eta(ray, record)),
+        };
+        let reflectance = fresnel_dielectric(wo.dot(&normal), eta);
+        if gen.gen::<f64>() < reflectance {
+            return reflect(wo, normal);
+        }
+        // the reflectance is 1 whenever there's no refracted direction, but rounding may still
+        // leave a ray at the critical angle without one
+        return match refract(wo, normal, eta) {
+            Some(wi) => wi,
+            None => reflect(wo, normal),
         };
-        let unit_dir = ray.dir.unit_vector();
-        let cos_theta = f64::min((-unit_dir).dot(&record.normal), 1.0);
-        let sin_theta = (1.0 - cos_theta.powf(2.0)).sqrt();
-
-        // determine whether to refract or reflect
-        let cannot_refract = refraction_ratio * sin_theta > 1.0;
-        let dir: Vec3;
-        if cannot_refract || (Self::reflectance(cos_theta, refraction_ratio) > gen.gen::<f64>()) {
-            dir = reflect(unit_dir, record.normal);
-        } else {
-            dir = refract(unit_dir, record.normal, refraction_ratio, cos_theta);
-        }
-
-        let scattered = Ray {
-            origin: record.p,
-            dir,
+    }
+
+    // the half vector of `wo` and `wi`, facing up, for a reflection or a refraction
+    fn half_vector(wo: Vec3, wi: Vec3, eta: f64) -> Vec3 {
+        let wm = match wi.z > 0.0 {
+            true => wo + wi,
+            false => wo + eta * wi,
+        };
+        let wm = wm.unit_vector();
+        return if wm.z < 0.0 { -wm } else { wm };
+    }
+
+    fn eval_rough(&self, ggx: &Ggx, eta: f64, wo: Vec3, wi: Vec3) -> f64 {
+        if wo.z <= 0.0 || wi.z == 0.0 {
+            return 0.0;
+        }
+        let wm = Self::half_vector(wo, wi, eta);
+        // microfacets which face away from either direction play no part
+        if wo.dot(&wm) <= 0.0 || wi.dot(&wm) * wi.z <= 0.0 {
+            return 0.0;
+        }
+        let fresnel = fresnel_dielectric(wo.dot(&wm), eta);
+        if wi.z > 0.0 {
+            return ggx.d(wm) * ggx.g(wo, wi) * fresnel / (4.0 * wo.z);
+        }
+        let denom = (wi.dot(&wm) + wo.dot(&wm) / eta).powi(2);
+        return ggx.d(wm) * ggx.g(wo, wi) * (1.0 - fresnel) * (wi.dot(&wm) * wo.dot(&wm)).abs()
+            / (wo.z * denom);
+    }
+
+    fn pdf_rough(&self, ggx: &Ggx, eta: f64, wo: Vec3, wi: Vec3) -> f64 {
+        if wo.z <= 0.0 || wi.z == 0.0 {
+            return 0.0;
+        }
+        let wm = Self::half_vector(wo, wi, eta);
+        if wo.dot(&wm) <= 0.0 || wi.dot(&wm) * wi.z <= 0.0 {
+            return 0.0;
+        }
+        // the normal is reflected off with probability equal to the reflectance
+        let fresnel = fresnel_dielectric(wo.dot(&wm), eta);
+        if wi.z > 0.0 {
+            return fresnel * ggx.visible_d(wo, wm) / (4.0 * wo.dot(&wm));
+        }
+        let denom = (wi.dot(&wm) + wo.dot(&wm) / eta).powi(2);
+        return (1.0 - fresnel) * ggx.visible_d(wo, wm) * wi.dot(&wm).abs() / denom;
+    }
+}
+
+impl Material for Dielectric {
+    fn is_dispersive(&self) -> bool {
+        self.dispersion.is_some()
+    }
+
+    fn scatter(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        gen: &mut dyn RngCore,
+    ) -> Option<ScatterRecord> {
+        let transmittance = self.transmittance(ray, record);
+        if self.roughness <= 0.0 {
+            return Some(ScatterRecord {
+                ray: Ray {
+                    origin: record.p,
+                    dir: self.scatter_smooth(ray, record, gen),
+                    time: ray.time,
+                    wavelength: ray.wavelength,
+                },
+                attenuation: transmittance,
+                pdf: None,
+                dispersive: self.dispersion.is_some(),
+            });
+        }
+
+        let ggx = Ggx::from_roughness(self.roughness);
+        let eta = self.eta(ray, record);
+        let onb = Onb::from_w(record.normal);
+        let wo = onb.to_local(-ray.dir.unit_vector());
+        if wo.z <= 0.0 {
+            return None;
+        }
+        let wm = ggx.sample_visible_normal(wo, (gen.gen(), gen.gen()));
+        let reflectance = fresnel_dielectric(wo.dot(&wm), eta);
+        let is_reflection = gen.gen::<f64>() < reflectance;
+        let wi = match is_reflection {
+            true => reflect(wo, wm),
+            false => refract(wo, wm, eta)?,
         };
+        // a direction which ends up on the wrong side of the surface is lost
+        if (wi.z > 0.0) != is_reflection {
+            return None;
+        }
+        let pdf = self.pdf_rough(&ggx, eta, wo, wi);
+        if pdf <= 0.0 {
+            return None;
+        }
+
+        return Some(ScatterRecord {
+            ray: Ray {
+                origin: record.p,
+                dir: onb.to_world(wi),
+                time: ray.time,
+                wavelength: ray.wavelength,
+            },
+            attenuation: self.eval_rough(&ggx, eta, wo, wi) / pdf * transmittance,
+            pdf: Some(pdf),
+            dispersive: self.dispersion.is_some(),
+        });
+    }
 
-        return Some((scattered, attenuation));
+    fn eval(&self, ray: &Ray, record: &HitRecord, dir: Vec3) -> Color {
+        if self.roughness <= 0.0 {
+            return Color::zeroes();
+        }
+        let onb = Onb::from_w(record.normal);
+        let wo = onb.to_local(-ray.dir.unit_vector());
+        let wi = onb.to_local(dir.unit_vector());
+        let ggx = Ggx::from_roughness(self.roughness);
+        let f = self.eval_rough(&ggx, self.eta(ray, record), wo, wi);
+        return f * self.transmittance(ray, record);
+    }
+
+    fn pdf(&self, ray: &Ray, record: &HitRecord, dir: Vec3) -> f64 {
+        if self.roughness <= 0.0 {
+            return 0.0;
+        }
+        let onb = Onb::from_w(record.normal);
+        let wo = onb.to_local(-ray.dir.unit_vector());
+        let wi = onb.to_local(dir.unit_vector());
+        let ggx = Ggx::from_roughness(self.roughness);
+        return self.pdf_rough(&ggx, self.eta(ray, record), wo, wi);
     }
 }
 
-impl Dielectric {
-    fn reflectance(cosine: f64, refraction_ratio: f64) -> f64 {
-        // Schlick's approximation
-        let r0 = ((1.0 - refraction_ratio) / (1.0 + refraction_ratio)).powf(2.0);
-        r0 + (1.0 - r0) * (1.0 - cosine).powf(5.0)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    // a ray leaving glass through a smooth-shaded face, whose shading normal leans so far that
+    // it faces away from the ray
+    #[test]
+    fn smooth_scatter_with_back_facing_normal() {
+        let glass = Dielectric::new(1.5);
+        let mut record = HitRecord::dummy();
+        record.normal = Vec3::new(0.0, 0.0, 1.0);
+        record.front_face = false;
+        let mut gen = ChaCha8Rng::seed_from_u64(0);
+        for cos in [0.1, 0.5, 0.7, 0.74, 0.9] {
+            let sin = f64::sqrt(1.0 - cos * cos);
+            let ray = Ray {
+                origin: Vec3::zeroes(),
+                dir: Vec3::new(sin, 0.0, cos),
+                time: 0.0,
+                wavelength: None,
+            };
+            for _ in 0..1000 {
+                let scatter = glass.scatter(&ray, &record, &mut gen).unwrap();
+                let length = scatter.ray.dir.length();
+                assert!(
+                    (length - 1.0).abs() < 1e-9,
+                    "direction of length {}",
+                    length
+                );
+            }
+        }
     }
 }
@@ -0,0 +1,35 @@
+use crate::hit_record::HitRecord;
+use crate::material::{Material, ScatterRecord};
+use crate::ray::Ray;
+use crate::texture::Texture;
+use crate::vec3::Color;
+use rand::RngCore;
+use std::sync::Arc;
+
+// a material which gives off light evenly in all directions, and reflects nothing.
+// light is only emitted from the front (outward-facing) side of the surface.
+pub struct DiffuseLight {
+    pub emit: Arc<dyn Texture>,
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _ray: &Ray,
+        _record: &HitRecord,
+        _gen: &mut dyn RngCore,
+    ) -> Option<ScatterRecord> {
+        None
+    }
+
+    fn emitted(&self, _ray: &Ray, record: &HitRecord) -> Color {
+        match record.front_face {
+            true => self.emit.value(record.u, record.v, record.p),
+            false => Color::zeroes(),
+        }
+    }
+
+    fn is_emissive(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,92 @@
+use crate::aabb::Aabb;
+use crate::hit_record::HitRecord;
+use crate::hittable::{area_pdf_to_solid_angle, Hittable};
+use crate::material::Material;
+use crate::onb::Onb;
+use crate::random::random_in_unit_disk;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use rand::RngCore;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+// a flat disk. the front face is the side `normal` points towards.
+pub struct Disk {
+    pub center: Point3,
+    pub normal: Vec3,
+    pub radius: f64,
+    pub mat_ptr: Arc<dyn Material + Send + Sync>,
+}
+
+// the bounds of a circle with the given center, unit normal and radius
+pub fn circle_bounding_box(center: Point3, normal: Vec3, radius: f64) -> Aabb {
+    // along each axis, the circle extends by the radius times the sine of the angle between
+    // the axis and the normal
+    let extent = |n: f64| radius * f64::sqrt(f64::max(1.0 - n * n, 0.0)) + 1e-4;
+    let e = Vec3::new(extent(normal.x), extent(normal.y), extent(normal.z));
+    return Aabb::new(center - e, center + e);
+}
+
+impl Hittable for Disk {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        record: &mut HitRecord,
+        _gen: &mut dyn RngCore,
+    ) -> bool {
+        let normal = self.normal.unit_vector();
+        let denom = ray.dir.dot(&normal);
+        if denom.abs() < 1e-12 {
+            return false;
+        }
+        let t = (self.center - ray.origin).dot(&normal) / denom;
+        if t < t_min || t_max < t {
+            return false;
+        }
+        let p = ray.at(t);
+        let offset = p - self.center;
+        if offset.length_squared() > self.radius * self.radius {
+            return false;
+        }
+
+        record.t = t;
+        record.p = p;
+        // polar co-ordinates: `u` is the angle around the center, `v` the distance from it
+        let basis = Onb::from_w(normal);
+        let phi = f64::atan2(offset.dot(&basis.v), offset.dot(&basis.u));
+        record.u = (phi + PI) / (2.0 * PI);
+        record.v = offset.length() / self.radius;
+        record.mat_ptr = Some(Arc::clone(&self.mat_ptr));
+        record.set_face_normal(ray, normal);
+        return true;
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(circle_bounding_box(
+            self.center,
+            self.normal.unit_vector(),
+            self.radius,
+        ))
+    }
+
+    fn is_light(&self) -> bool {
+        self.mat_ptr.is_emissive()
+    }
+
+    fn pdf_value(&self, ray: &Ray, gen: &mut dyn RngCore) -> f64 {
+        let mut record = HitRecord::dummy();
+        if !self.hit(ray, 0.001, f64::INFINITY, &mut record, gen) {
+            return 0.0;
+        }
+        let area = PI * self.radius * self.radius;
+        return area_pdf_to_solid_angle(ray, record.t, record.normal, area);
+    }
+
+    fn random(&self, origin: Point3, gen: &mut dyn RngCore) -> Vec3 {
+        let basis = Onb::from_w(self.normal);
+        let offset = self.radius * basis.to_world(random_in_unit_disk(gen));
+        return self.center + offset - origin;
+    }
+}
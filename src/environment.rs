@@ -1,7 +1,27 @@
+use crate::atmosphere::Atmosphere;
+use crate::background::{Background, Sun};
 use crate::camera::Camera;
 use crate::hittable_list::HittableList;
+use crate::vec3::{Color, Vec3};
 
 pub struct Environment {
     pub camera: Camera,
     pub world: HittableList,
+    pub background: Background,
+    // a haze filling the space between objects, if any
+    pub atmosphere: Option<Atmosphere>,
+    // the emitters in `world` which are sampled directly, along with the sun
+    pub lights: HittableList,
+    pub sun: Option<Sun>,
+}
+
+impl Environment {
+    // the light arriving along rays which escape the scene in direction `dir`
+    pub fn sky(&self, dir: Vec3) -> Color {
+        let color = self.background.color(dir);
+        match &self.sun {
+            Some(sun) => color + sun.radiance(dir),
+            None => color,
+        }
+    }
 }
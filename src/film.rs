@@ -0,0 +1,193 @@
+use crate::image::Image;
+use crate::vec3::Color;
+use std::f64::consts::PI;
+
+// how samples are spread over the pixels around them. each filter is separable, the product of
+// a 1D filter along x and along y, and is zero beyond `radius` pixels from its centre.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FilterKind {
+    // every sample within the radius counts equally. with a radius of 0.5, each pixel is the
+    // plain average of its own samples.
+    Box,
+    // falls off linearly to zero at the radius
+    Tent,
+    // a gaussian with a standard deviation of a third of the radius, shifted down to reach zero
+    // at the radius
+    Gaussian,
+    // the Mitchell-Netravali cubic with B = C = 1/3, stretched over the radius. its small
+    // negative lobes sharpen edges.
+    Mitchell,
+    // a sinc windowed by a wider sinc (Lanczos-a, with a the radius). the sharpest of the
+    // filters, at the cost of some ringing around edges.
+    Lanczos,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Filter {
+    pub kind: FilterKind,
+    // in pixels
+    pub radius: f64,
+}
+
+impl Filter {
+    // the filter named `name`, with its usual radius
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (kind, radius) = match name {
+            "box" => (FilterKind::Box, 0.5),
+            "tent" => (FilterKind::Tent, 1.0),
+            "gaussian" => (FilterKind::Gaussian, 1.5),
+            "mitchell" => (FilterKind::Mitchell, 2.0),
+            "lanczos" => (FilterKind::Lanczos, 3.0),
+            _ => return None,
+        };
+        return Some(Filter { kind, radius });
+    }
+
+    // the weight of a sample at offset (x, y) from a pixel centre
+    pub fn eval(&self, x: f64, y: f64) -> f64 {
+        self.eval_1d(x) * self.eval_1d(y)
+    }
+
+    fn eval_1d(&self, x: f64) -> f64 {
+        let r = self.radius;
+        let x = x.abs();
+        if x >= r {
+            return 0.0;
+        }
+        match self.kind {
+            FilterKind::Box => 1.0,
+            FilterKind::Tent => 1.0 - x / r,
+            FilterKind::Gaussian => {
+                let sigma = r / 3.0;
+                let gaussian = |x: f64| f64::exp(-x * x / (2.0 * sigma * sigma));
+                gaussian(x) - gaussian(r)
+            }
+            FilterKind::Mitchell => mitchell(2.0 * x / r, 1.0 / 3.0, 1.0 / 3.0),
+            FilterKind::Lanczos => sinc(x) * sinc(x / r),
+        }
+    }
+}
+
+// the Mitchell-Netravali cubic, which is non-zero over [0, 2)
+fn mitchell(x: f64, b: f64, c: f64) -> f64 {
+    let value = match x < 1.0 {
+        true => {
+            (12.0 - 9.0 * b - 6.0 * c) * x * x * x
+                + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+                + (6.0 - 2.0 * b)
+        }
+        false => {
+            (-b - 6.0 * c) * x * x * x
+                + (6.0 * b + 30.0 * c) * x * x
+                + (-12.0 * b - 48.0 * c) * x
+                + (8.0 * b + 24.0 * c)
+        }
+    };
+    return value / 6.0;
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-5 {
+        return 1.0;
+    }
+    return f64::sin(PI * x) / (PI * x);
+}
+
+// a rectangle of pixels which accumulates samples. each sample is added to every pixel whose
+// centre is within the filter's radius, weighted by the filter, and each pixel's value is its
+// weighted sum divided by the sum of the weights.
+pub struct Film {
+    // the position of the film's top left pixel within the whole image
+    x0: i64,
+    y0: i64,
+    width: usize,
+    height: usize,
+    filter: Filter,
+    sums: Vec<Color>,
+    weights: Vec<f64>,
+}
+
+impl Film {
+    pub fn new(x0: i64, y0: i64, width: usize, height: usize, filter: Filter) -> Self {
+        Self {
+            x0,
+            y0,
+            width,
+            height,
+            filter,
+            sums: vec![Color::zeroes(); width * height],
+            weights: vec![0.0; width * height],
+        }
+    }
+
+    // the film covering the pixels [x0, x1) x [y0, y1) of an image `width` by `height`, along
+    // with the pixels around them which samples inside them can reach
+    pub fn for_tile(
+        (x0, y0, x1, y1): (u32, u32, u32, u32),
+        width: u32,
+        height: u32,
+        filter: Filter,
+    ) -> Self {
+        let margin = filter.radius.ceil() as i64;
+        let fx0 = i64::max(x0 as i64 - margin, 0);
+        let fy0 = i64::max(y0 as i64 - margin, 0);
+        let fx1 = i64::min(x1 as i64 + margin, width as i64);
+        let fy1 = i64::min(y1 as i64 + margin, height as i64);
+        Self::new(fx0, fy0, (fx1 - fx0) as usize, (fy1 - fy0) as usize, filter)
+    }
+
+    // add a sample at (x, y) in image co-ordinates, where pixel (i, j) spans [i, i + 1) x
+    // [j, j + 1) and row 0 is the top of the image
+    pub fn add_sample(&mut self, x: f64, y: f64, color: Color) {
+        // the pixels whose centres (at i + 0.5) could be within the radius
+        let r = self.filter.radius;
+        let i0 = i64::max((x - 0.5 - r).floor() as i64, self.x0);
+        let i1 = i64::min((x - 0.5 + r).ceil() as i64, self.x0 + self.width as i64 - 1);
+        let j0 = i64::max((y - 0.5 - r).floor() as i64, self.y0);
+        let j1 = i64::min(
+            (y - 0.5 + r).ceil() as i64,
+            self.y0 + self.height as i64 - 1,
+        );
+        for j in j0..=j1 {
+            for i in i0..=i1 {
+                let weight = self.filter.eval(x - (i as f64 + 0.5), y - (j as f64 + 0.5));
+                if weight == 0.0 {
+                    continue;
+                }
+                let idx = (j - self.y0) as usize * self.width + (i - self.x0) as usize;
+                self.sums[idx] += weight * color;
+                self.weights[idx] += weight;
+            }
+        }
+    }
+
+    // add the samples gathered by `other`, where the two overlap
+    pub fn merge(&mut self, other: &Film) {
+        for row in 0..other.height {
+            let j = other.y0 + row as i64 - self.y0;
+            if j < 0 || j >= self.height as i64 {
+                continue;
+            }
+            for col in 0..other.width {
+                let i = other.x0 + col as i64 - self.x0;
+                if i < 0 || i >= self.width as i64 {
+                    continue;
+                }
+                let idx = j as usize * self.width + i as usize;
+                self.sums[idx] += other.sums[row * other.width + col];
+                self.weights[idx] += other.weights[row * other.width + col];
+            }
+        }
+    }
+
+    pub fn to_image(&self) -> Image {
+        let mut image = Image::zeroes(self.width as u32, self.height as u32);
+        for (idx, (sum, weight)) in self.sums.iter().zip(&self.weights).enumerate() {
+            // negative lobes can, rarely, leave a pixel with no weight at all
+            if *weight != 0.0 {
+                image.pixels[idx / self.width][idx % self.width] = *sum / *weight;
+            }
+        }
+        return image;
+    }
+}
@@ -0,0 +1,44 @@
+use crate::aabb::Aabb;
+use crate::hit_record::HitRecord;
+use crate::hittable::Hittable;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use rand::RngCore;
+
+// swaps the front and back faces of a surface, e.g. to make a one-sided light face the other way
+pub struct FlipFace {
+    pub object: Box<dyn Hittable>,
+}
+
+impl Hittable for FlipFace {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        record: &mut HitRecord,
+        gen: &mut dyn RngCore,
+    ) -> bool {
+        if !self.object.hit(ray, t_min, t_max, record, gen) {
+            return false;
+        }
+        record.front_face = !record.front_face;
+        return true;
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.object.bounding_box()
+    }
+
+    fn is_light(&self) -> bool {
+        self.object.is_light()
+    }
+
+    fn pdf_value(&self, ray: &Ray, gen: &mut dyn RngCore) -> f64 {
+        self.object.pdf_value(ray, gen)
+    }
+
+    fn random(&self, origin: Point3, gen: &mut dyn RngCore) -> Vec3 {
+        self.object.random(origin, gen)
+    }
+}
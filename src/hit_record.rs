@@ -7,6 +7,8 @@ pub struct HitRecord {
     pub p: Point3,
     pub normal: Vec3,
     pub t: f64,
+    pub u: f64, // surface co-ordinates of the hit point
+    pub v: f64,
     pub front_face: bool,
     pub mat_ptr: Option<Arc<dyn Material + Send + Sync>>,
 }
@@ -18,6 +20,8 @@ impl HitRecord {
             p: Point3::zeroes(),
             normal: Vec3::zeroes(),
             t: 0.0,
+            u: 0.0,
+            v: 0.0,
             front_face: false,
             mat_ptr: None,
         }
@@ -36,6 +40,8 @@ impl HitRecord {
         self.p = other.p;
         self.normal = other.normal;
         self.t = other.t;
+        self.u = other.u;
+        self.v = other.v;
         self.front_face = other.front_face;
         self.mat_ptr = match &(other.mat_ptr) {
             Some(val) => Some(Arc::clone(val)),
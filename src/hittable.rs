@@ -1,7 +1,50 @@
+use crate::aabb::Aabb;
 use crate::hit_record::HitRecord;
 use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use rand::RngCore;
 
 // trait for all hittable surfaces
 pub trait Hittable: Send + Sync {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, record: &mut HitRecord) -> bool;
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        record: &mut HitRecord,
+        gen: &mut dyn RngCore,
+    ) -> bool;
+
+    // a box enclosing the surface, or `None` if it is unbounded (e.g. an infinite plane)
+    fn bounding_box(&self) -> Option<Aabb>;
+
+    // whether the surface gives off light, and can be sampled directly with `random` and
+    // `pdf_value`. only simple shapes support this; other emitters are still found by rays
+    // which happen to hit them.
+    fn is_light(&self) -> bool {
+        false
+    }
+
+    // the probability density (per unit solid angle, as seen from the ray origin) with which
+    // `random` would choose the ray's direction
+    fn pdf_value(&self, _ray: &Ray, _gen: &mut dyn RngCore) -> f64 {
+        0.0
+    }
+
+    // a random direction from `origin` towards the surface. only lights are ever sampled, and
+    // every surface which can be one overrides this.
+    fn random(&self, _origin: Point3, _gen: &mut dyn RngCore) -> Vec3 {
+        unreachable!("`random` called on a surface which is not a light")
+    }
+}
+
+// the density, per unit solid angle, of a point chosen uniformly over a surface of the given
+// `area`, where the ray towards that point hit the surface at `t`, with surface normal `normal`
+pub fn area_pdf_to_solid_angle(ray: &Ray, t: f64, normal: Vec3, area: f64) -> f64 {
+    let distance_squared = t * t * ray.dir.length_squared();
+    let cosine = f64::abs(ray.dir.dot(&normal)) / (ray.dir.length() * normal.length());
+    if cosine < 1e-8 {
+        return 0.0;
+    }
+    return distance_squared / (cosine * area);
 }
@@ -1,10 +1,15 @@
+use crate::aabb::Aabb;
+use crate::bvh::BvhNode;
 use crate::hit_record::HitRecord;
 use crate::hittable::Hittable;
 use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use rand::{Rng, RngCore};
+use std::sync::Arc;
 
 // a struct to store all the "Hittable" objects in our scene.
 pub struct HittableList {
-    components: Vec<Box<dyn Hittable>>,
+    components: Vec<Arc<dyn Hittable>>,
 }
 
 impl HittableList {
@@ -13,22 +18,62 @@ impl HittableList {
     }
 
     pub fn add(&mut self, obj: Box<dyn Hittable>) {
-        self.components.push(obj);
+        self.components.push(Arc::from(obj));
     }
 
-    pub fn clear(&mut self) {
-        self.components.clear();
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    // a list of the components which can be sampled as lights, sharing them with this list
+    pub fn lights(&self) -> HittableList {
+        HittableList {
+            components: self
+                .components
+                .iter()
+                .filter(|obj| obj.is_light())
+                .cloned()
+                .collect(),
+        }
+    }
+
+    // move every bounded component into a bounding volume hierarchy. unbounded components
+    // (which cannot be placed in the hierarchy) are kept alongside it and tested linearly.
+    pub fn into_bvh(self) -> HittableList {
+        let (bounded, unbounded): (Vec<_>, Vec<_>) = self
+            .components
+            .into_iter()
+            .partition(|obj| obj.bounding_box().is_some());
+
+        let mut list = HittableList {
+            components: unbounded,
+        };
+        if !bounded.is_empty() {
+            list.add(Box::new(BvhNode::new(bounded)));
+        }
+        return list;
     }
 }
 
 impl Hittable for HittableList {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, record: &mut HitRecord) -> bool {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        record: &mut HitRecord,
+        gen: &mut dyn RngCore,
+    ) -> bool {
         let mut temp_rec: HitRecord = HitRecord::dummy();
         let mut hit_anything: bool = false;
         let mut closest_so_far = t_max;
 
         for item in self.components.iter() {
-            if item.hit(ray, t_min, closest_so_far, &mut temp_rec) {
+            if item.hit(ray, t_min, closest_so_far, &mut temp_rec, gen) {
                 hit_anything = true;
                 closest_so_far = temp_rec.t;
                 record.copy_from(&temp_rec);
@@ -37,4 +82,34 @@ impl Hittable for HittableList {
 
         return hit_anything;
     }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let mut bbox = Aabb::empty();
+        for item in self.components.iter() {
+            bbox = bbox.surrounding_box(&item.bounding_box()?);
+        }
+        if self.components.is_empty() {
+            return None;
+        }
+        return Some(bbox);
+    }
+
+    // as a light, the list picks one of its components at random, with equal probability
+    fn is_light(&self) -> bool {
+        !self.components.is_empty() && self.components.iter().all(|obj| obj.is_light())
+    }
+
+    fn pdf_value(&self, ray: &Ray, gen: &mut dyn RngCore) -> f64 {
+        let sum: f64 = self
+            .components
+            .iter()
+            .map(|obj| obj.pdf_value(ray, gen))
+            .sum();
+        return sum / self.components.len() as f64;
+    }
+
+    fn random(&self, origin: Point3, gen: &mut dyn RngCore) -> Vec3 {
+        let idx = gen.gen_range(0..self.components.len());
+        return self.components[idx].random(origin, gen);
+    }
 }
@@ -1,5 +1,4 @@
 use crate::vec3::Color;
-use std::ops::AddAssign;
 
 pub struct Image {
     pub width: u32,
@@ -23,14 +22,53 @@ impl Image {
             pixels,
         }
     }
+
+    // sample the image at (x, y), where (0, 0) is the top left corner and (1, 1) the bottom
+    // right, interpolating between the four nearest pixel centres
+    pub fn bilinear(&self, x: f64, y: f64, wrap_x: WrapMode, wrap_y: WrapMode) -> Color {
+        let x = x * self.width as f64 - 0.5;
+        let y = y * self.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+
+        let texel = |i: i64, j: i64| {
+            let i = wrap_x.apply(i, self.width as i64);
+            let j = wrap_y.apply(j, self.height as i64);
+            self.pixels[j][i]
+        };
+        let (i, j) = (x0 as i64, y0 as i64);
+        let top = (1.0 - fx) * texel(i, j) + fx * texel(i + 1, j);
+        let bottom = (1.0 - fx) * texel(i, j + 1) + fx * texel(i + 1, j + 1);
+        return (1.0 - fy) * top + fy * bottom;
+    }
 }
 
-impl AddAssign for Image {
-    fn add_assign(&mut self, rhs: Self) {
-        for i in 0..self.height {
-            for j in 0..self.width {
-                self.pixels[i as usize][j as usize] += rhs.pixels[i as usize][j as usize];
+// how pixel co-ordinates beyond the edge of an image are brought back inside it
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WrapMode {
+    // tile the image
+    Repeat,
+    // extend the edge pixels outwards
+    Clamp,
+    // tile the image, flipping every other copy
+    Mirror,
+}
+
+impl WrapMode {
+    // map the pixel index `i` into [0, n)
+    fn apply(self, i: i64, n: i64) -> usize {
+        let i = match self {
+            WrapMode::Repeat => i.rem_euclid(n),
+            WrapMode::Clamp => i.clamp(0, n - 1),
+            WrapMode::Mirror => {
+                let m = i.rem_euclid(2 * n);
+                if m < n {
+                    m
+                } else {
+                    2 * n - 1 - m
+                }
             }
-        }
+        };
+        return i as usize;
     }
 }
@@ -0,0 +1,860 @@
+// reading and writing images. the output format is chosen from the file's extension:
+//   - `.ppm`: binary (P6) portable pixmap, 8 bits per channel
+//   - `.png`: 8 or 16 bits per channel
+//   - `.hdr`: Radiance RGBE, which keeps the linear, unclamped radiance values
+// the low dynamic range formats are tone mapped (see `ToneMapping`), clamped to [0, 1] and sRGB
+// encoded. the HDR format is written as it is.
+// images can be read back from any of the same formats (see `read_image`), for use as textures
+// and environment maps.
+
+use crate::color::{srgb_eotf, srgb_oetf};
+use crate::image::Image;
+use crate::inflate::zlib_decompress;
+use crate::tonemap::ToneMapping;
+use crate::util::clamp;
+use crate::vec3::Color;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Error, ErrorKind, Read, Write};
+use std::path::Path;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ImageFormat {
+    Ppm,
+    Png8,
+    Png16,
+    Hdr,
+}
+
+impl ImageFormat {
+    // pick a format from the extension of `path`. `bit_depth` only applies to PNG.
+    pub fn from_path(path: &Path, bit_depth: u32) -> Result<Self, String> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+        let format = match extension.as_deref() {
+            Some("ppm") => ImageFormat::Ppm,
+            Some("png") if bit_depth == 16 => ImageFormat::Png16,
+            Some("png") => ImageFormat::Png8,
+            Some("hdr") => ImageFormat::Hdr,
+            _ => {
+                return Err(format!(
+                    "cannot tell the image format of `{}`: use a .ppm, .png or .hdr extension",
+                    path.display()
+                ))
+            }
+        };
+        if bit_depth != 8 && format != ImageFormat::Png16 {
+            return Err("a bit depth of 16 is only supported for PNG output".to_string());
+        }
+        return Ok(format);
+    }
+}
+
+// how the samples of a PNG or PPM image map to values. Radiance HDR images always hold linear
+// values.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Encoding {
+    // sRGB encoded colors, which are converted back to linear
+    Srgb,
+    // plain numbers in [0, 1], such as a metallic-roughness map or a mask, taken as they are
+    Linear,
+}
+
+impl Encoding {
+    // the value of a sample, given as a fraction of the largest sample
+    fn decode(self, x: f64) -> f64 {
+        match self {
+            Encoding::Srgb => srgb_eotf(x),
+            Encoding::Linear => x,
+        }
+    }
+}
+
+pub fn write_image(
+    path: &Path,
+    image: &Image,
+    format: ImageFormat,
+    tone_mapping: &ToneMapping,
+) -> std::io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    match format {
+        ImageFormat::Ppm => write_ppm(&mut out, &tone_mapping.apply(image))?,
+        ImageFormat::Png8 => write_png(&mut out, &tone_mapping.apply(image), 8)?,
+        ImageFormat::Png16 => write_png(&mut out, &tone_mapping.apply(image), 16)?,
+        ImageFormat::Hdr => write_hdr(&mut out, image)?,
+    }
+    return out.flush();
+}
+
+// clamp a linear color channel into [0, 1] and sRGB encode it
+fn encode(x: f64) -> f64 {
+    srgb_oetf(clamp(x, 0.0, 1.0))
+}
+
+fn to_8bit(x: f64) -> u8 {
+    (256.0 * f64::min(encode(x), 0.999)) as u8
+}
+
+fn to_16bit(x: f64) -> u16 {
+    (65535.0 * encode(x)).round() as u16
+}
+
+pub fn write_ppm(out: &mut impl Write, image: &Image) -> std::io::Result<()> {
+    write!(out, "P6\n{} {}\n255\n", image.width, image.height)?;
+    for row in image.pixels.iter() {
+        let bytes: Vec<u8> = row
+            .iter()
+            .flat_map(|c| [to_8bit(c.x), to_8bit(c.y), to_8bit(c.z)])
+            .collect();
+        out.write_all(&bytes)?;
+    }
+    return Ok(());
+}
+
+// write an RGB PNG with the given bit depth (8 or 16)
+pub fn write_png(out: &mut impl Write, image: &Image, bit_depth: u8) -> std::io::Result<()> {
+    let bytes_per_pixel = 3 * (bit_depth as usize / 8);
+
+    // each scanline is a filter-type byte followed by the filtered pixel bytes
+    let mut raw = Vec::new();
+    let mut prev_row = vec![0u8; image.width as usize * bytes_per_pixel];
+    for row in image.pixels.iter() {
+        let mut row_bytes = Vec::with_capacity(prev_row.len());
+        for c in row.iter() {
+            for x in [c.x, c.y, c.z] {
+                match bit_depth {
+                    16 => row_bytes.extend_from_slice(&to_16bit(x).to_be_bytes()),
+                    _ => row_bytes.push(to_8bit(x)),
+                }
+            }
+        }
+        let (filter, filtered) = png_filter_row(&row_bytes, &prev_row, bytes_per_pixel);
+        raw.push(filter);
+        raw.extend_from_slice(&filtered);
+        prev_row = row_bytes;
+    }
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&image.width.to_be_bytes());
+    header.extend_from_slice(&image.height.to_be_bytes());
+    // bit depth, color type (RGB), compression, filter method, interlacing
+    header.extend_from_slice(&[bit_depth, 2, 0, 0, 0]);
+
+    out.write_all(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'])?;
+    write_png_chunk(out, b"IHDR", &header)?;
+    write_png_chunk(out, b"IDAT", &zlib_compress(&raw))?;
+    write_png_chunk(out, b"IEND", &[])?;
+    return Ok(());
+}
+
+fn write_png_chunk(out: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> std::io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let crc = crc32(&[&kind[..], data].concat());
+    out.write_all(&crc.to_be_bytes())?;
+    return Ok(());
+}
+
+// apply each of the PNG filters to a scanline, and keep the one with the smallest sum of
+// absolute (signed) residuals, which usually compresses best
+fn png_filter_row(row: &[u8], prev: &[u8], bpp: usize) -> (u8, Vec<u8>) {
+    let mut best: (u8, Vec<u8>) = (0, row.to_vec());
+    let mut best_score = u64::MAX;
+    for filter in 0..5u8 {
+        let filtered: Vec<u8> = (0..row.len())
+            .map(|i| {
+                let a = if i >= bpp { row[i - bpp] } else { 0 };
+                let b = prev[i];
+                let c = if i >= bpp { prev[i - bpp] } else { 0 };
+                let predicted = match filter {
+                    0 => 0,
+                    1 => a,
+                    2 => b,
+                    3 => ((a as u16 + b as u16) / 2) as u8,
+                    _ => paeth(a, b, c),
+                };
+                row[i].wrapping_sub(predicted)
+            })
+            .collect();
+        let score = filtered
+            .iter()
+            .map(|&x| (x as i8).unsigned_abs() as u64)
+            .sum();
+        if score < best_score {
+            best_score = score;
+            best = (filter, filtered);
+        }
+    }
+    return best;
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    return !crc;
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    return (b << 16) | a;
+}
+
+// a zlib stream holding a single deflate block, compressed with LZ77 and the fixed huffman codes
+fn zlib_compress(data: &[u8]) -> Vec<u8> {
+    let mut bits = BitWriter::new();
+    bits.write(0x0178, 16); // zlib header: deflate with a 32K window, no preset dictionary
+
+    bits.write(1, 1); // final block
+    bits.write(1, 2); // fixed huffman codes
+
+    const WINDOW: usize = 32768;
+    const MIN_MATCH: usize = 3;
+    const MAX_MATCH: usize = 258;
+    const MAX_CHAIN: usize = 32;
+    const HASH_BITS: u32 = 15;
+
+    // `head[h]` is the latest position whose next three bytes hash to `h`, and `prev[i % WINDOW]`
+    // is the previous position with the same hash as position `i`
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; WINDOW];
+    let hash = |i: usize| {
+        let key = (data[i] as u32) << 16 | (data[i + 1] as u32) << 8 | data[i + 2] as u32;
+        (key.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize
+    };
+    let insert = |i: usize, head: &mut [usize], prev: &mut [usize]| {
+        if i + MIN_MATCH <= data.len() {
+            let h = hash(i);
+            prev[i % WINDOW] = head[h];
+            head[h] = i;
+        }
+    };
+
+    let mut i = 0;
+    while i < data.len() {
+        // find the longest earlier match for the bytes starting at `i`
+        let mut best_len = 0;
+        let mut best_dist = 0;
+        if i + MIN_MATCH <= data.len() {
+            let max_len = usize::min(MAX_MATCH, data.len() - i);
+            let mut candidate = head[hash(i)];
+            let mut chain = 0;
+            while candidate != usize::MAX && i - candidate <= WINDOW && chain < MAX_CHAIN {
+                let len = (0..max_len)
+                    .take_while(|&k| data[candidate + k] == data[i + k])
+                    .count();
+                if len > best_len {
+                    best_len = len;
+                    best_dist = i - candidate;
+                    if len == max_len {
+                        break;
+                    }
+                }
+                candidate = prev[candidate % WINDOW];
+                chain += 1;
+            }
+        }
+
+        if best_len >= MIN_MATCH {
+            bits.write_length(best_len);
+            bits.write_distance(best_dist);
+            for k in 0..best_len {
+                insert(i + k, &mut head, &mut prev);
+            }
+            i += best_len;
+        } else {
+            bits.write_literal(data[i] as u16);
+            insert(i, &mut head, &mut prev);
+            i += 1;
+        }
+    }
+    bits.write_literal(256); // end of block
+
+    let mut bytes = bits.finish();
+    bytes.extend_from_slice(&adler32(data).to_be_bytes());
+    return bytes;
+}
+
+// packs bits least-significant first, as deflate expects
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u64,
+    count: u32,
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u32; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u32; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            bytes: Vec::new(),
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    fn write(&mut self, value: u32, n: u32) {
+        self.buffer |= (value as u64) << self.count;
+        self.count += n;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    // huffman codes are stored most-significant bit first
+    fn write_code(&mut self, code: u32, n: u32) {
+        self.write(code.reverse_bits() >> (32 - n), n);
+    }
+
+    // write a literal/length symbol with the fixed huffman code
+    fn write_literal(&mut self, symbol: u16) {
+        let symbol = symbol as u32;
+        match symbol {
+            0..=143 => self.write_code(0x30 + symbol, 8),
+            144..=255 => self.write_code(0x190 + symbol - 144, 9),
+            256..=279 => self.write_code(symbol - 256, 7),
+            _ => self.write_code(0xc0 + symbol - 280, 8),
+        }
+    }
+
+    fn write_length(&mut self, len: usize) {
+        let idx = LENGTH_BASE
+            .iter()
+            .rposition(|&base| base as usize <= len)
+            .unwrap();
+        self.write_literal(257 + idx as u16);
+        self.write((len - LENGTH_BASE[idx] as usize) as u32, LENGTH_EXTRA[idx]);
+    }
+
+    fn write_distance(&mut self, dist: usize) {
+        let idx = DIST_BASE
+            .iter()
+            .rposition(|&base| base as usize <= dist)
+            .unwrap();
+        self.write_code(idx as u32, 5);
+        self.write((dist - DIST_BASE[idx] as usize) as u32, DIST_EXTRA[idx]);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        return self.bytes;
+    }
+}
+
+// write a Radiance RGBE image, with each pixel stored as a shared exponent and three mantissas
+pub fn write_hdr(out: &mut impl Write, image: &Image) -> std::io::Result<()> {
+    write!(
+        out,
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+        image.height, image.width
+    )?;
+    for row in image.pixels.iter() {
+        let bytes: Vec<u8> = row.iter().flat_map(|c| to_rgbe(*c)).collect();
+        out.write_all(&bytes)?;
+    }
+    return Ok(());
+}
+
+fn to_rgbe(color: Color) -> [u8; 4] {
+    let (r, g, b) = (color.x.max(0.0), color.y.max(0.0), color.z.max(0.0));
+    let max = r.max(g).max(b);
+    if max < 1e-32 || !max.is_finite() {
+        return [0, 0, 0, 0];
+    }
+    // find `e` such that max = m * 2^e with m in [0.5, 1)
+    let e = (max.log2().floor() as i32 + 1).min(127);
+    let scale = 256.0 / 2f64.powi(e);
+    return [
+        (r * scale) as u8,
+        (g * scale) as u8,
+        (b * scale) as u8,
+        (e + 128) as u8,
+    ];
+}
+
+// read an image, in a format chosen from the file's extension. the samples of the low dynamic
+// range formats are decoded according to `encoding`.
+pub fn read_image(path: &Path, encoding: Encoding) -> std::io::Result<Image> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("hdr") => read_hdr(path),
+        Some("png") => read_png(path, encoding),
+        Some("ppm") => read_ppm(path, encoding),
+        _ => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "unsupported image format: use a .ppm, .png or .hdr file",
+        )),
+    }
+}
+
+// read a binary (P6) or plain text (P3) portable pixmap
+pub fn read_ppm(path: &Path, encoding: Encoding) -> std::io::Result<Image> {
+    let mut data = Vec::new();
+    File::open(path)?.read_to_end(&mut data)?;
+    let invalid = |message: &str| Error::new(ErrorKind::InvalidData, message.to_string());
+
+    // the header (and, for P3, the pixels) are whitespace-separated tokens, where `#` starts
+    // a comment running to the end of the line
+    let mut pos = 0;
+    let next_token = |pos: &mut usize| -> Option<String> {
+        loop {
+            match data.get(*pos)? {
+                b'#' => {
+                    while *data.get(*pos)? != b'\n' {
+                        *pos += 1;
+                    }
+                }
+                byte if byte.is_ascii_whitespace() => *pos += 1,
+                _ => break,
+            }
+        }
+        let start = *pos;
+        while data
+            .get(*pos)
+            .is_some_and(|byte| !byte.is_ascii_whitespace())
+        {
+            *pos += 1;
+        }
+        return Some(String::from_utf8_lossy(&data[start..*pos]).into_owned());
+    };
+    let number = |pos: &mut usize| -> std::io::Result<u32> {
+        match next_token(pos).map(|token| token.parse::<u32>()) {
+            Some(Ok(n)) => Ok(n),
+            _ => Err(invalid("invalid PPM header")),
+        }
+    };
+
+    let magic = next_token(&mut pos);
+    let binary = match magic.as_deref() {
+        Some("P6") => true,
+        Some("P3") => false,
+        _ => return Err(invalid("not a P3 or P6 PPM file")),
+    };
+    let width = number(&mut pos)?;
+    let height = number(&mut pos)?;
+    let max_value = number(&mut pos)?;
+    if width == 0 || height == 0 || max_value == 0 || max_value > 65535 {
+        return Err(invalid("invalid PPM header"));
+    }
+
+    let num_samples = 3 * width as usize * height as usize;
+    let samples: Vec<u32> = if binary {
+        // exactly one whitespace byte separates the header from the pixels
+        pos += 1;
+        let bytes_per_sample = if max_value < 256 { 1 } else { 2 };
+        let end = pos + num_samples * bytes_per_sample;
+        if end > data.len() {
+            return Err(invalid("unexpected end of PPM data"));
+        }
+        match bytes_per_sample {
+            1 => data[pos..end].iter().map(|&b| u32::from(b)).collect(),
+            _ => data[pos..end]
+                .chunks(2)
+                .map(|b| u32::from(u16::from_be_bytes([b[0], b[1]])))
+                .collect(),
+        }
+    } else {
+        (0..num_samples)
+            .map(|_| number(&mut pos))
+            .collect::<std::io::Result<_>>()?
+    };
+
+    let mut image = Image::zeroes(width, height);
+    let pixels = image.pixels.iter_mut().flat_map(|row| row.iter_mut());
+    for (pixel, rgb) in pixels.zip(samples.chunks(3)) {
+        let channel =
+            |i: usize| encoding.decode(f64::from(rgb[i].min(max_value)) / f64::from(max_value));
+        *pixel = Color::new(channel(0), channel(1), channel(2));
+    }
+    return Ok(image);
+}
+
+// read a PNG of any bit depth and color type, except for interlaced images. any alpha channel
+// is ignored.
+pub fn read_png(path: &Path, encoding: Encoding) -> std::io::Result<Image> {
+    let mut data = Vec::new();
+    File::open(path)?.read_to_end(&mut data)?;
+    let invalid = |message: &str| Error::new(ErrorKind::InvalidData, message.to_string());
+
+    if !data.starts_with(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n']) {
+        return Err(invalid("not a PNG file"));
+    }
+    let mut header: Option<&[u8]> = None;
+    let mut palette: &[u8] = &[];
+    let mut compressed = Vec::new();
+    let mut pos = 8;
+    loop {
+        if pos + 12 > data.len() {
+            return Err(invalid("unexpected end of PNG file"));
+        }
+        let len = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
+        let end = pos + 8 + len;
+        if end + 4 > data.len() {
+            return Err(invalid("unexpected end of PNG file"));
+        }
+        let kind = &data[pos + 4..pos + 8];
+        let chunk = &data[pos + 8..end];
+        let crc = u32::from_be_bytes(data[end..end + 4].try_into().unwrap());
+        if crc32(&data[pos + 4..end]) != crc {
+            return Err(invalid("corrupt PNG chunk"));
+        }
+        match kind {
+            b"IHDR" => header = Some(chunk),
+            b"PLTE" => palette = chunk,
+            b"IDAT" => compressed.extend_from_slice(chunk),
+            b"IEND" => break,
+            // other chunks (gamma, color profiles, text, ...) are ignored
+            _ => {}
+        }
+        pos = end + 4;
+    }
+
+    let header = match header {
+        Some(header) if header.len() == 13 => header,
+        _ => return Err(invalid("missing PNG header")),
+    };
+    let width = u32::from_be_bytes(header[0..4].try_into().unwrap());
+    let height = u32::from_be_bytes(header[4..8].try_into().unwrap());
+    let (bit_depth, color_type) = (header[8] as usize, header[9]);
+    if header[12] != 0 {
+        return Err(invalid("interlaced PNGs are not supported"));
+    }
+    let channels = match (color_type, bit_depth) {
+        (0, 1 | 2 | 4 | 8 | 16) => 1,
+        (2, 8 | 16) => 3,
+        (3, 1 | 2 | 4 | 8) => 1,
+        (4, 8 | 16) => 2,
+        (6, 8 | 16) => 4,
+        _ => return Err(invalid("unsupported PNG color type or bit depth")),
+    };
+    if width == 0 || height == 0 {
+        return Err(invalid("invalid image size"));
+    }
+
+    let raw = zlib_decompress(&compressed).map_err(|err| invalid(&err))?;
+    let stride = (width as usize * channels * bit_depth).div_ceil(8);
+    let bpp = usize::max(1, channels * bit_depth / 8);
+    if raw.len() < height as usize * (stride + 1) {
+        return Err(invalid("not enough PNG image data"));
+    }
+
+    let max_value = ((1u32 << bit_depth) - 1) as f64;
+    let mut image = Image::zeroes(width, height);
+    let mut prev_row = vec![0u8; stride];
+    for (row, scanline) in image.pixels.iter_mut().zip(raw.chunks(stride + 1)) {
+        let current = png_unfilter_row(scanline[0], &scanline[1..], &prev_row, bpp)
+            .ok_or_else(|| invalid("invalid PNG filter type"))?;
+
+        // the `i`th sample of the row, packed most significant bits first
+        let sample = |i: usize| -> u32 {
+            match bit_depth {
+                16 => u32::from(u16::from_be_bytes([current[2 * i], current[2 * i + 1]])),
+                8 => u32::from(current[i]),
+                _ => {
+                    let bit = i * bit_depth;
+                    let shift = 8 - bit_depth - bit % 8;
+                    u32::from(current[bit / 8] >> shift) & ((1 << bit_depth) - 1)
+                }
+            }
+        };
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let channel =
+                |c: usize| encoding.decode(f64::from(sample(x * channels + c)) / max_value);
+            *pixel = match color_type {
+                0 | 4 => {
+                    let gray = channel(0);
+                    Color::new(gray, gray, gray)
+                }
+                3 => {
+                    let index = sample(x) as usize;
+                    match palette.get(3 * index..3 * index + 3) {
+                        Some(rgb) => {
+                            let channel = |c: usize| encoding.decode(f64::from(rgb[c]) / 255.0);
+                            Color::new(channel(0), channel(1), channel(2))
+                        }
+                        None => return Err(invalid("PNG palette index out of range")),
+                    }
+                }
+                _ => Color::new(channel(0), channel(1), channel(2)),
+            };
+        }
+        prev_row = current;
+    }
+    return Ok(image);
+}
+
+// undo one of the PNG filters on a scanline, given the previous (unfiltered) scanline
+fn png_unfilter_row(filter: u8, row: &[u8], prev: &[u8], bpp: usize) -> Option<Vec<u8>> {
+    let mut out: Vec<u8> = Vec::with_capacity(row.len());
+    for (i, &x) in row.iter().enumerate() {
+        let a = if i >= bpp { out[i - bpp] } else { 0 };
+        let b = prev[i];
+        let c = if i >= bpp { prev[i - bpp] } else { 0 };
+        let predicted = match filter {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => ((a as u16 + b as u16) / 2) as u8,
+            4 => paeth(a, b, c),
+            _ => return None,
+        };
+        out.push(x.wrapping_add(predicted));
+    }
+    return Some(out);
+}
+
+// read a Radiance RGBE image, in either flat or run-length encoded form
+pub fn read_hdr(path: &Path) -> std::io::Result<Image> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let invalid = |message: &str| Error::new(ErrorKind::InvalidData, message.to_string());
+
+    // the header is a list of `key=value` lines, ended by a blank line
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    if !line.starts_with("#?") {
+        return Err(invalid("not a Radiance HDR file"));
+    }
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(invalid("unexpected end of header"));
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(format) = line.strip_prefix("FORMAT=") {
+            if format != "32-bit_rle_rgbe" {
+                return Err(invalid("only the RGBE pixel format is supported"));
+            }
+        }
+    }
+
+    // the resolution line. only the standard orientation (rows top to bottom) is supported.
+    line.clear();
+    reader.read_line(&mut line)?;
+    let (height, width) = match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["-Y", h, "+X", w] => match (h.parse::<u32>(), w.parse::<u32>()) {
+            (Ok(h), Ok(w)) if h > 0 && w > 0 => (h, w),
+            _ => return Err(invalid("invalid image size")),
+        },
+        _ => return Err(invalid("unsupported image orientation")),
+    };
+
+    let mut image = Image::zeroes(width, height);
+    let mut scanline = vec![[0u8; 4]; width as usize];
+    for row in image.pixels.iter_mut() {
+        read_hdr_scanline(&mut reader, &mut scanline)?;
+        for (pixel, rgbe) in row.iter_mut().zip(scanline.iter()) {
+            *pixel = from_rgbe(*rgbe);
+        }
+    }
+    return Ok(image);
+}
+
+fn read_hdr_scanline(reader: &mut impl Read, scanline: &mut [[u8; 4]]) -> std::io::Result<()> {
+    let invalid = || Error::new(ErrorKind::InvalidData, "invalid run-length encoding");
+    let width = scanline.len();
+
+    let mut first = [0u8; 4];
+    reader.read_exact(&mut first)?;
+    let is_rle =
+        first[0] == 2 && first[1] == 2 && (first[2] as usize) << 8 | first[3] as usize == width;
+    if !is_rle || !(8..32768).contains(&width) {
+        // flat: every pixel is stored as four bytes
+        scanline[0] = first;
+        for pixel in scanline[1..].iter_mut() {
+            reader.read_exact(pixel)?;
+        }
+        return Ok(());
+    }
+
+    // run-length encoded: each of the four channels is stored in turn, as a series of runs
+    // (a count above 128, then one byte to repeat) and literal spans (a count, then the bytes)
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let mut count = [0u8; 1];
+            reader.read_exact(&mut count)?;
+            let count = count[0] as usize;
+            if count > 128 {
+                let n = count - 128;
+                if x + n > width {
+                    return Err(invalid());
+                }
+                let mut value = [0u8; 1];
+                reader.read_exact(&mut value)?;
+                for pixel in scanline[x..x + n].iter_mut() {
+                    pixel[channel] = value[0];
+                }
+                x += n;
+            } else {
+                if count == 0 || x + count > width {
+                    return Err(invalid());
+                }
+                let mut values = vec![0u8; count];
+                reader.read_exact(&mut values)?;
+                for (pixel, value) in scanline[x..x + count].iter_mut().zip(values) {
+                    pixel[channel] = value;
+                }
+                x += count;
+            }
+        }
+    }
+    return Ok(());
+}
+
+fn from_rgbe(rgbe: [u8; 4]) -> Color {
+    if rgbe[3] == 0 {
+        return Color::zeroes();
+    }
+    let scale = 2f64.powi(rgbe[3] as i32 - 128 - 8);
+    return Color::new(
+        (rgbe[0] as f64 + 0.5) * scale,
+        (rgbe[1] as f64 + 0.5) * scale,
+        (rgbe[2] as f64 + 0.5) * scale,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // an image with smooth gradients, sharp edges and repeated rows, so that every filter type
+    // and both literals and back-references are likely to be used
+    fn test_image() -> Image {
+        let mut image = Image::zeroes(37, 23);
+        for (y, row) in image.pixels.iter_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let stripe = if (x / 4 + y / 8) % 2 == 0 { 1.0 } else { 0.0 };
+                *pixel = Color::new(x as f64 / 36.0, (y / 2) as f64 / 11.0, stripe);
+            }
+        }
+        return image;
+    }
+
+    // write `image` as a PNG to a temporary file and read it back
+    fn png_round_trip(image: &Image, bit_depth: u8, encoding: Encoding) -> Image {
+        let path = std::env::temp_dir().join(format!(
+            "raytrace-round-trip-{}-{}-{:?}.png",
+            std::process::id(),
+            bit_depth,
+            encoding
+        ));
+        let mut out = BufWriter::new(File::create(&path).unwrap());
+        write_png(&mut out, image, bit_depth).unwrap();
+        out.flush().unwrap();
+        drop(out);
+        let read = read_png(&path, encoding);
+        std::fs::remove_file(&path).unwrap();
+        return read.unwrap();
+    }
+
+    #[test]
+    fn zlib_round_trip() {
+        let mut data: Vec<u8> = (0..5000u32).map(|i| (i * i % 251) as u8).collect();
+        data.extend([7; 1000]);
+        data.extend_from_within(..3000);
+        assert_eq!(zlib_decompress(&zlib_compress(&data)).unwrap(), data);
+        assert_eq!(
+            zlib_decompress(&zlib_compress(&[])).unwrap(),
+            Vec::<u8>::new()
+        );
+    }
+
+    #[test]
+    fn png_8bit_round_trip() {
+        let image = test_image();
+        let read = png_round_trip(&image, 8, Encoding::Srgb);
+        assert_eq!((read.width, read.height), (image.width, image.height));
+        for (row, read_row) in image.pixels.iter().zip(&read.pixels) {
+            for (c, r) in row.iter().zip(read_row) {
+                // the stored bytes are the same
+                assert_eq!(
+                    [to_8bit(c.x), to_8bit(c.y), to_8bit(c.z)],
+                    [to_8bit(r.x), to_8bit(r.y), to_8bit(r.z)]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn png_16bit_round_trip() {
+        let image = test_image();
+        let read = png_round_trip(&image, 16, Encoding::Srgb);
+        assert_eq!((read.width, read.height), (image.width, image.height));
+        for (row, read_row) in image.pixels.iter().zip(&read.pixels) {
+            for (c, r) in row.iter().zip(read_row) {
+                let error = (*c - *r).length();
+                assert!(error < 1e-3, "read {:?} back as {:?}", c, r);
+            }
+        }
+    }
+
+    #[test]
+    fn png_linear_read() {
+        let image = test_image();
+        let read = png_round_trip(&image, 16, Encoding::Linear);
+        for (row, read_row) in image.pixels.iter().zip(&read.pixels) {
+            for (c, r) in row.iter().zip(read_row) {
+                // the stored sRGB values, as they are
+                let stored = Color::new(encode(c.x), encode(c.y), encode(c.z));
+                let error = (stored - *r).length();
+                assert!(error < 1e-4, "read {:?} back as {:?}", stored, r);
+            }
+        }
+    }
+}
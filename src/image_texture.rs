@@ -0,0 +1,16 @@
+use crate::image::{Image, WrapMode};
+use crate::texture::Texture;
+use crate::vec3::{Color, Point3};
+
+// an image stretched over the surface's (u, v) co-ordinates, with (0, 0) at the bottom left
+// of the image. texels are blended bilinearly, and `wrap` decides what lies outside [0, 1].
+pub struct ImageTexture {
+    pub image: Image,
+    pub wrap: WrapMode,
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: Point3) -> Color {
+        self.image.bilinear(u, 1.0 - v, self.wrap, self.wrap)
+    }
+}
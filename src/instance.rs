@@ -0,0 +1,89 @@
+use crate::aabb::Aabb;
+use crate::hit_record::HitRecord;
+use crate::hittable::Hittable;
+use crate::mat4::Mat4;
+use crate::ray::Ray;
+use crate::vec3::Point3;
+use rand::RngCore;
+use std::sync::Arc;
+
+// places a shared object in the scene with an affine transform, so that the same geometry can
+// appear many times in different positions, orientations and sizes. rays are taken into the
+// object's own space to be intersected, and the hit is brought back out into world space.
+pub struct Instance {
+    object: Arc<dyn Hittable>,
+    transform: Mat4,
+    inverse: Mat4,
+    // the inverse transpose, which takes normals from object space into world space
+    normal_transform: Mat4,
+    bbox: Option<Aabb>,
+}
+
+impl Instance {
+    // panics if `transform` can't be inverted, e.g. because it scales by zero
+    pub fn new(object: Arc<dyn Hittable>, transform: Mat4) -> Self {
+        let inverse = transform
+            .inverse()
+            .expect("Instance transform must be invertible");
+        let bbox = object
+            .bounding_box()
+            .map(|bbox| transform_box(&bbox, &transform));
+        Self {
+            object,
+            transform,
+            inverse,
+            normal_transform: inverse.transpose(),
+            bbox,
+        }
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        record: &mut HitRecord,
+        gen: &mut dyn RngCore,
+    ) -> bool {
+        // the direction is left unnormalised, so that distances along the ray (t) are the same
+        // in both spaces
+        let local_ray = Ray {
+            origin: self.inverse.transform_point(ray.origin),
+            dir: self.inverse.transform_vector(ray.dir),
+            time: ray.time,
+            wavelength: ray.wavelength,
+        };
+        if !self.object.hit(&local_ray, t_min, t_max, record, gen) {
+            return false;
+        }
+
+        record.p = self.transform.transform_point(record.p);
+        // the object has already turned the normal to face against the ray, and the inverse
+        // transpose preserves that, so `front_face` carries over unchanged
+        record.normal = self
+            .normal_transform
+            .transform_vector(record.normal)
+            .unit_vector();
+        return true;
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.bbox
+    }
+}
+
+// the world-space box around the eight transformed corners of `bbox`
+fn transform_box(bbox: &Aabb, transform: &Mat4) -> Aabb {
+    let mut result = Aabb::empty();
+    for corner in 0..8 {
+        let pick = |axis: usize| match corner & (1 << axis) {
+            0 => bbox.minimum[axis],
+            _ => bbox.maximum[axis],
+        };
+        let p = Point3::new(pick(0), pick(1), pick(2));
+        result = result.include_point(transform.transform_point(p));
+    }
+    return result;
+}
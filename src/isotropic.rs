@@ -0,0 +1,45 @@
+use crate::hit_record::HitRecord;
+use crate::material::{Material, ScatterRecord};
+use crate::random::random_on_unit_sphere;
+use crate::ray::Ray;
+use crate::texture::Texture;
+use crate::vec3::{Color, Vec3};
+use rand::RngCore;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+// the phase function of a participating medium which scatters light equally in every
+// direction, regardless of where it came from
+pub struct Isotropic {
+    pub albedo: Arc<dyn Texture>,
+}
+
+impl Material for Isotropic {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        gen: &mut dyn RngCore,
+    ) -> Option<ScatterRecord> {
+        let scattered = Ray {
+            origin: record.p,
+            dir: random_on_unit_sphere(gen),
+            time: ray.time,
+            wavelength: ray.wavelength,
+        };
+        Some(ScatterRecord {
+            ray: scattered,
+            attenuation: self.albedo.value(record.u, record.v, record.p),
+            pdf: Some(1.0 / (4.0 * PI)),
+            dispersive: false,
+        })
+    }
+
+    fn eval(&self, _ray: &Ray, record: &HitRecord, _dir: Vec3) -> Color {
+        self.albedo.value(record.u, record.v, record.p) / (4.0 * PI)
+    }
+
+    fn pdf(&self, _ray: &Ray, _record: &HitRecord, _dir: Vec3) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+}
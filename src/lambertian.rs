@@ -1,25 +1,53 @@
 use crate::hit_record::HitRecord;
-use crate::material::Material;
-use crate::random::random_on_unit_sphere;
+use crate::material::{Material, ScatterRecord};
+use crate::onb::Onb;
+use crate::random::random_cosine_direction;
 use crate::ray::Ray;
-use crate::vec3::Color;
-use rand::rngs::ThreadRng;
+use crate::texture::Texture;
+use crate::vec3::{Color, Vec3};
+use rand::RngCore;
+use std::f64::consts::PI;
+use std::sync::Arc;
 
 // a diffuse material which randomly reflect rays
 pub struct Lambertian {
-    pub albedo: Color,
+    pub albedo: Arc<dyn Texture>,
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _ray: &Ray, record: &HitRecord, gen: &mut ThreadRng) -> Option<(Ray, Color)> {
-        let mut scatter_dir = record.normal + random_on_unit_sphere(gen);
-        if scatter_dir.near_zero() {
-            scatter_dir = record.normal;
-        }
+    fn scatter(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        gen: &mut dyn RngCore,
+    ) -> Option<ScatterRecord> {
+        // a cosine-weighted direction about the normal
+        let scatter_dir = Onb::from_w(record.normal).to_world(random_cosine_direction(gen));
         let scattered = Ray {
             origin: record.p,
             dir: scatter_dir,
+            time: ray.time,
+            wavelength: ray.wavelength,
         };
-        Some((scattered, self.albedo))
+        // the cosine and the 1/pi of the BSDF cancel with the density, leaving the albedo
+        Some(ScatterRecord {
+            attenuation: self.albedo.value(record.u, record.v, record.p),
+            pdf: Some(self.pdf(ray, record, scatter_dir)),
+            dispersive: false,
+            ray: scattered,
+        })
+    }
+
+    fn eval(&self, _ray: &Ray, record: &HitRecord, dir: Vec3) -> Color {
+        let cosine = record.normal.dot(&dir.unit_vector());
+        if cosine <= 0.0 {
+            return Color::zeroes();
+        }
+        return self.albedo.value(record.u, record.v, record.p) * cosine / PI;
+    }
+
+    fn pdf(&self, _ray: &Ray, record: &HitRecord, dir: Vec3) -> f64 {
+        let cosine = record.normal.dot(&dir.unit_vector());
+        return f64::max(cosine, 0.0) / PI;
     }
 }
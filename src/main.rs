@@ -1,88 +1,153 @@
-use crate::camera::Camera;
-use crate::vec3::Point3;
-use std::sync::Arc;
+// explicit `return` statements are used throughout for readability
+#![allow(clippy::needless_return)]
 
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::background::Background;
 use crate::build_random_scene::build_random_scene;
-use crate::environment::Environment;
-use crate::image::Image;
+use crate::camera::CameraSettings;
+use crate::cli::{parse_args, CliError, USAGE};
+use crate::image_io::{write_image, ImageFormat};
 use crate::render::render;
-use crate::util::write_color;
+use crate::scene::{load_scene, Scene};
+use crate::tonemap::ToneMapping;
 
+mod aabb;
+mod aabox;
+mod aarect;
+mod atmosphere;
+mod background;
 mod build_random_scene;
+mod bvh;
 mod camera;
+mod cli;
+mod coated;
+mod color;
+mod conductor;
+mod cone;
+mod constant_medium;
+mod cylinder;
 mod dielectric;
+mod diffuse_light;
+mod disk;
 mod environment;
+mod film;
+mod flip_face;
 mod hit_record;
 mod hittable;
 mod hittable_list;
 mod image;
+mod image_io;
+mod image_texture;
+mod inflate;
+mod instance;
+mod isotropic;
 mod lambertian;
+mod mat4;
 mod material;
+mod mesh;
 mod metal;
+mod microfacet;
+mod mix_material;
+mod moving_sphere;
+mod noise_texture;
+mod obj;
+mod onb;
+mod perlin;
+mod plane;
+mod principled;
 mod random;
 mod ray;
 mod render;
+mod sampler;
+mod scene;
+mod spectrum;
 mod sphere;
+mod texture;
+mod thin_dielectric;
+mod tonemap;
+mod triangle;
 mod util;
 mod vec3;
+mod warp;
 
-const SAMPLES_PER_PIXEL: u32 = 16;
-const IMAGE_WIDTH: u32 = 900;
-const MAX_DEPTH: u32 = 100;
-const NUM_THREADS: u32 = 8;
+// the stream of the seed's random numbers used to build the random scene
+const RANDOM_SCENE_STREAM: u64 = 1;
 
 fn main() {
-    // image + camera
-    let aspect_ratio: f64 = 3.0 / 2.0;
-    let aperture = 0.1;
-    let look_from = Point3::new(13.0, 2.0, 3.0);
-    let look_at = Point3::new(0.0, 0.0, 0.0);
-    let dist_to_focus = 10.0;
-    let camera = Camera::new(
-        look_from,
-        look_at,
-        Point3::new(0.0, 1.0, 0.0),
-        20.0,
-        aspect_ratio,
-        aperture,
-        dist_to_focus,
-    );
-
-    let image_height: u32 = ((IMAGE_WIDTH as f64) / aspect_ratio) as u32;
-
-    // world
-    let world = build_random_scene();
-    let env = Environment { camera, world };
+    let settings = match parse_args(std::env::args().skip(1)) {
+        Ok(settings) => settings,
+        Err(CliError::Help) => {
+            print!("{}", USAGE);
+            return;
+        }
+        Err(err) => exit_with_error(&format!("{} (run with --help for usage)", err)),
+    };
 
-    // wrap the environment in an Arc, so that it can be passed between threads.
-    let env = Arc::new(env);
+    // load the scene file, or fall back to a random scene
+    let mut scene = match &settings.scene_path {
+        Some(path) => match load_scene(path) {
+            Ok(scene) => scene,
+            Err(err) => exit_with_error(&format!("{}: {}", path.display(), err)),
+        },
+        None => {
+            // the scene draws from its own stream of the seed, so it never shares random numbers
+            // with the render, however the render comes to use the seed
+            let mut gen = ChaCha8Rng::seed_from_u64(settings.render.seed);
+            gen.set_stream(RANDOM_SCENE_STREAM);
+            Scene {
+                camera: CameraSettings::default(),
+                world: build_random_scene(&mut gen),
+                background: Background::default(),
+                atmosphere: None,
+                sun: None,
+            }
+        }
+    };
+    settings.camera.apply(&mut scene.camera);
+    if let Err(err) = scene.camera.validate() {
+        exit_with_error(&err);
+    }
 
-    // spawn `NUM_THREADS` threads, each of which will render an image. average the resulting images
-    // to get the final image.
-    let mut threads = Vec::new();
-    for _ in 0..NUM_THREADS {
-        let env = Arc::clone(&env);
-        threads.push(std::thread::spawn(move || {
-            let image = render(env, SAMPLES_PER_PIXEL, IMAGE_WIDTH, MAX_DEPTH);
-            return image;
-        }));
+    let image_height = ((settings.render.image_width as f64) / scene.camera.aspect_ratio) as u32;
+    if image_height < 2 {
+        exit_with_error("the image must be at least two pixels high");
     }
 
-    // create an image of all zeroes
-    let mut image = Image::zeroes(IMAGE_WIDTH, image_height);
+    // camera + world, with the objects placed in a bounding volume hierarchy
+    let env = scene.into_environment();
 
-    // collect the images from the threads
-    for thread in threads {
-        let image_i = thread.join().unwrap();
-        image += image_i;
+    // render!
+    let output = render(&env, &settings.render);
+    if settings.render.adaptive.is_some() {
+        eprintln!("Mean samples per pixel: {:.1}", output.mean_samples());
     }
 
-    // render!
-    println!("P3 {} {} 255", IMAGE_WIDTH, image_height);
-    for row in image.pixels {
-        for pixel in row {
-            write_color(pixel, NUM_THREADS, 0.5);
+    eprintln!("Writing image...");
+    if let Err(err) = write_image(
+        &settings.output_path,
+        &output.image,
+        settings.output_format,
+        &settings.tone_mapping,
+    ) {
+        exit_with_error(&format!(
+            "could not write {}: {}",
+            settings.output_path.display(),
+            err
+        ));
+    }
+    if let Some(path) = &settings.sample_map_path {
+        // the format was checked when parsing the arguments
+        let format = ImageFormat::from_path(path, 8).unwrap();
+        let map = output.sample_map();
+        if let Err(err) = write_image(path, &map, format, &ToneMapping::default()) {
+            exit_with_error(&format!("could not write {}: {}", path.display(), err));
         }
     }
-    eprintln!("Writing image...");
+}
+
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    std::process::exit(1);
 }
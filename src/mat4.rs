@@ -0,0 +1,133 @@
+use crate::util::degrees_to_radians;
+use crate::vec3::{Point3, Vec3};
+use std::ops::Mul;
+
+// a 4x4 matrix of homogeneous co-ordinates, stored row-major, used for affine transforms.
+// points are treated as column vectors, so `a * b` applies `b` first and then `a`.
+#[derive(Debug, Copy, Clone)]
+pub struct Mat4 {
+    pub m: [[f64; 4]; 4],
+}
+
+impl Mat4 {
+    pub fn identity() -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        Self { m }
+    }
+
+    pub fn translation(offset: Vec3) -> Self {
+        let mut result = Self::identity();
+        result.m[0][3] = offset.x;
+        result.m[1][3] = offset.y;
+        result.m[2][3] = offset.z;
+        return result;
+    }
+
+    // scale by a (possibly different) factor along each axis
+    pub fn scaling(factors: Vec3) -> Self {
+        let mut result = Self::identity();
+        result.m[0][0] = factors.x;
+        result.m[1][1] = factors.y;
+        result.m[2][2] = factors.z;
+        return result;
+    }
+
+    // rotate by `angle` degrees about `axis`, anticlockwise when looking back along the axis
+    pub fn rotation(axis: Vec3, angle: f64) -> Self {
+        let a = axis.unit_vector();
+        let (sin, cos) = degrees_to_radians(angle).sin_cos();
+        let t = 1.0 - cos;
+        let mut result = Self::identity();
+        result.m[0][0] = t * a.x * a.x + cos;
+        result.m[0][1] = t * a.x * a.y - sin * a.z;
+        result.m[0][2] = t * a.x * a.z + sin * a.y;
+        result.m[1][0] = t * a.x * a.y + sin * a.z;
+        result.m[1][1] = t * a.y * a.y + cos;
+        result.m[1][2] = t * a.y * a.z - sin * a.x;
+        result.m[2][0] = t * a.x * a.z - sin * a.y;
+        result.m[2][1] = t * a.y * a.z + sin * a.x;
+        result.m[2][2] = t * a.z * a.z + cos;
+        return result;
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() {
+                *x = self.m[j][i];
+            }
+        }
+        Self { m }
+    }
+
+    // the inverse, by Gauss-Jordan elimination with partial pivoting. returns `None` if the
+    // matrix is singular (or very nearly so).
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.m;
+        let mut inv = Self::identity().m;
+        for col in 0..4 {
+            // swap the row with the largest entry in this column into place
+            let pivot = (col..4)
+                .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+                .unwrap();
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1.0 / a[col][col];
+            for j in 0..4 {
+                a[col][j] *= scale;
+                inv[col][j] *= scale;
+            }
+            for row in 0..4 {
+                if row == col {
+                    continue;
+                }
+                let factor = a[row][col];
+                for j in 0..4 {
+                    a[row][j] -= factor * a[col][j];
+                    inv[row][j] -= factor * inv[col][j];
+                }
+            }
+        }
+        return Some(Self { m: inv });
+    }
+
+    // transform a position, including the translation
+    pub fn transform_point(&self, p: Point3) -> Point3 {
+        let m = &self.m;
+        Point3::new(
+            m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3],
+            m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3],
+            m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3],
+        )
+    }
+
+    // transform a direction, which is unaffected by the translation
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+}
+
+impl Mul<Mat4> for Mat4 {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() {
+                *x = (0..4).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+            }
+        }
+        Self { m }
+    }
+}
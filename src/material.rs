@@ -1,9 +1,65 @@
 use crate::hit_record::HitRecord;
 use crate::ray::Ray;
-use crate::vec3::Color;
-use rand::rngs::ThreadRng;
+use crate::vec3::{Color, Vec3};
+use rand::RngCore;
+
+// the result of scattering a ray off a surface
+pub struct ScatterRecord {
+    pub ray: Ray,
+    // the BSDF times the cosine term, divided by the probability density of choosing `ray`.
+    // this is the factor by which the light arriving back along `ray` is scaled.
+    pub attenuation: Color,
+    // the probability density (per unit solid angle) of choosing `ray`, or `None` if it was
+    // the only direction possible (as for a perfect mirror), in which case lights can't
+    // usefully be sampled
+    pub pdf: Option<f64>,
+    // whether the scattering depends on the ray's wavelength (as refraction through a prism
+    // does), so that the path can only carry that one wavelength onwards
+    pub dispersive: bool,
+}
 
 pub trait Material {
     // take a ray and a hit-record. we can absorb, OR return a scattered ray with an attenuation
-    fn scatter(&self, ray: &Ray, record: &HitRecord, gen: &mut ThreadRng) -> Option<(Ray, Color)>;
+    fn scatter(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        gen: &mut dyn RngCore,
+    ) -> Option<ScatterRecord>;
+
+    // the BSDF times the cosine term, for light arriving from direction `dir` and leaving back
+    // along `ray`. materials which only scatter into single directions return black.
+    fn eval(&self, _ray: &Ray, _record: &HitRecord, _dir: Vec3) -> Color {
+        Color::zeroes()
+    }
+
+    // the probability density with which `scatter` would choose direction `dir`
+    fn pdf(&self, _ray: &Ray, _record: &HitRecord, _dir: Vec3) -> f64 {
+        0.0
+    }
+
+    // light given off by the surface at the hit point, towards the ray origin.
+    // most materials don't emit anything.
+    fn emitted(&self, _ray: &Ray, _record: &HitRecord) -> Color {
+        Color::zeroes()
+    }
+
+    // whether `scatter` may choose a single direction (with no `pdf`) while the material still
+    // scatters light over a range of other directions, as a clear coat over a rough base does.
+    // lights are then worth sampling even after such a bounce.
+    fn has_mixed_lobes(&self) -> bool {
+        false
+    }
+
+    // whether any scattering by the material can be `dispersive`, for materials which combine
+    // others and weigh their directions against one another
+    fn is_dispersive(&self) -> bool {
+        false
+    }
+
+    // whether `emitted` is ever non-zero, so that surfaces made of this material are worth
+    // sampling directly as lights
+    fn is_emissive(&self) -> bool {
+        false
+    }
 }
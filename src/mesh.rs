@@ -0,0 +1,133 @@
+use crate::aabb::Aabb;
+use crate::hit_record::HitRecord;
+use crate::hittable::Hittable;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::triangle::{
+    intersect_triangle, random_on_triangle, triangle_bounding_box, triangle_pdf_value,
+};
+use crate::vec3::{Point3, Vec3};
+use rand::RngCore;
+use std::sync::Arc;
+
+type ArcMaterial = Arc<dyn Material + Send + Sync>;
+
+// a triangle mesh. vertex attributes are stored once in shared buffers, and each face refers to
+// them by index, so that neighbouring triangles share their vertices.
+pub struct Mesh {
+    pub positions: Vec<Point3>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Vec<(f64, f64)>,
+    pub materials: Vec<ArcMaterial>,
+    pub faces: Vec<Face>,
+}
+
+pub struct Face {
+    pub positions: [usize; 3],
+    // per-vertex normals, for smooth shading. faces without them are shaded flat.
+    pub normals: Option<[usize; 3]>,
+    pub uvs: Option<[usize; 3]>,
+    // index into `Mesh::materials`
+    pub material: usize,
+}
+
+impl Mesh {
+    // split the mesh into one hittable per face, all sharing the mesh's buffers
+    pub fn into_triangles(self) -> Vec<Box<dyn Hittable>> {
+        let mesh = Arc::new(self);
+        return (0..mesh.faces.len())
+            .map(|face| {
+                let triangle = MeshTriangle {
+                    mesh: Arc::clone(&mesh),
+                    face,
+                };
+                Box::new(triangle) as Box<dyn Hittable>
+            })
+            .collect();
+    }
+}
+
+pub struct MeshTriangle {
+    mesh: Arc<Mesh>,
+    face: usize,
+}
+
+impl MeshTriangle {
+    fn vertices(&self) -> (Point3, Point3, Point3) {
+        let [i0, i1, i2] = self.mesh.faces[self.face].positions;
+        let p = &self.mesh.positions;
+        (p[i0], p[i1], p[i2])
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        record: &mut HitRecord,
+        _gen: &mut dyn RngCore,
+    ) -> bool {
+        let (v0, v1, v2) = self.vertices();
+        let (t, b1, b2) = match intersect_triangle(ray, v0, v1, v2, t_min, t_max) {
+            Some(hit) => hit,
+            None => return false,
+        };
+        let b0 = 1.0 - b1 - b2;
+        let face = &self.mesh.faces[self.face];
+
+        record.t = t;
+        record.p = ray.at(t);
+        (record.u, record.v) = match face.uvs {
+            Some([i0, i1, i2]) => {
+                let uvs = &self.mesh.uvs;
+                (
+                    b0 * uvs[i0].0 + b1 * uvs[i1].0 + b2 * uvs[i2].0,
+                    b0 * uvs[i0].1 + b1 * uvs[i1].1 + b2 * uvs[i2].1,
+                )
+            }
+            None => (b1, b2),
+        };
+        record.mat_ptr = Some(Arc::clone(&self.mesh.materials[face.material]));
+
+        // which side was hit is decided by the true (geometric) normal
+        let outward_normal = (v1 - v0).cross(&(v2 - v0)).unit_vector();
+        record.set_face_normal(ray, outward_normal);
+
+        // for smooth shading, interpolate the vertex normals, keeping them on the side of the
+        // surface facing the ray
+        if let Some([i0, i1, i2]) = face.normals {
+            let normals = &self.mesh.normals;
+            let shading_normal = b0 * normals[i0] + b1 * normals[i1] + b2 * normals[i2];
+            if !shading_normal.near_zero() {
+                let shading_normal = shading_normal.unit_vector();
+                record.normal = match shading_normal.dot(&record.normal) < 0.0 {
+                    true => -shading_normal,
+                    false => shading_normal,
+                };
+            }
+        }
+        return true;
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let (v0, v1, v2) = self.vertices();
+        Some(triangle_bounding_box(v0, v1, v2))
+    }
+
+    fn is_light(&self) -> bool {
+        let face = &self.mesh.faces[self.face];
+        self.mesh.materials[face.material].is_emissive()
+    }
+
+    fn pdf_value(&self, ray: &Ray, _gen: &mut dyn RngCore) -> f64 {
+        let (v0, v1, v2) = self.vertices();
+        triangle_pdf_value(ray, v0, v1, v2)
+    }
+
+    fn random(&self, origin: Point3, gen: &mut dyn RngCore) -> Vec3 {
+        let (v0, v1, v2) = self.vertices();
+        random_on_triangle(origin, v0, v1, v2, gen)
+    }
+}
@@ -1,12 +1,14 @@
 use crate::hit_record::HitRecord;
-use crate::material::Material;
+use crate::material::{Material, ScatterRecord};
 use crate::random::random_in_unit_sphere;
 use crate::ray::Ray;
-use crate::vec3::{Color, Vec3};
-use rand::rngs::ThreadRng;
+use crate::texture::Texture;
+use crate::vec3::Vec3;
+use rand::RngCore;
+use std::sync::Arc;
 
 pub struct Metal {
-    pub albedo: Color,
+    pub albedo: Arc<dyn Texture>,
     pub fuzz: f64,
 }
 
@@ -15,14 +17,28 @@ pub fn reflect(v: Vec3, n: Vec3) -> Vec3 {
 }
 
 impl Material for Metal {
-    fn scatter(&self, ray: &Ray, record: &HitRecord, gen: &mut ThreadRng) -> Option<(Ray, Color)> {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        gen: &mut dyn RngCore,
+    ) -> Option<ScatterRecord> {
         let reflected = reflect(ray.dir.unit_vector(), record.normal);
         let scattered = Ray {
             origin: record.p,
             dir: reflected + self.fuzz * random_in_unit_sphere(gen),
+            time: ray.time,
+            wavelength: ray.wavelength,
         };
         if scattered.dir.dot(&record.normal) > 0.0 {
-            return Some((scattered, self.albedo));
+            let attenuation = self.albedo.value(record.u, record.v, record.p);
+            // the fuzzed direction has no simple density, so is treated like a mirror's
+            return Some(ScatterRecord {
+                ray: scattered,
+                attenuation,
+                pdf: None,
+                dispersive: false,
+            });
         }
         return None;
     }
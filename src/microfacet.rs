@@ -0,0 +1,164 @@
+// the pieces shared by the microfacet materials: the GGX (Trowbridge-Reitz) distribution of
+// microfacet normals, Smith's masking-shadowing function and Fresnel reflectance.
+//
+// directions are given in a local frame with the surface normal along +z, pointing away from
+// the surface.
+
+use crate::vec3::{Color, Vec3};
+use std::f64::consts::PI;
+
+// below this, the distribution is so narrow that evaluating it loses all precision
+const MIN_ALPHA: f64 = 1e-3;
+
+pub struct Ggx {
+    // the width of the distribution, which is the square of the perceptual roughness
+    pub alpha: f64,
+}
+
+impl Ggx {
+    // the distribution for a perceptual roughness in [0, 1], as used by glTF and most tools
+    pub fn from_roughness(roughness: f64) -> Self {
+        Self {
+            alpha: f64::max(roughness * roughness, MIN_ALPHA),
+        }
+    }
+
+    // the density of microfacet normals `wm`, per unit of projected area
+    pub fn d(&self, wm: Vec3) -> f64 {
+        if wm.z <= 0.0 {
+            return 0.0;
+        }
+        let a2 = self.alpha * self.alpha;
+        let t = wm.z * wm.z * (a2 - 1.0) + 1.0;
+        return a2 / (PI * t * t);
+    }
+
+    // Smith's auxiliary function, the area of microfacets hidden from `w` relative to those
+    // visible
+    fn lambda(&self, w: Vec3) -> f64 {
+        let cos2 = w.z * w.z;
+        let tan2 = f64::max(1.0 - cos2, 0.0) / cos2;
+        return 0.5 * (f64::sqrt(1.0 + self.alpha * self.alpha * tan2) - 1.0);
+    }
+
+    // the fraction of microfacets visible from `w`
+    pub fn g1(&self, w: Vec3) -> f64 {
+        return 1.0 / (1.0 + self.lambda(w));
+    }
+
+    // the fraction of microfacets visible from both `wo` and `wi`, with the height-correlated
+    // form of Smith's function
+    pub fn g(&self, wo: Vec3, wi: Vec3) -> f64 {
+        return 1.0 / (1.0 + self.lambda(wo) + self.lambda(wi));
+    }
+
+    // the density of normals `wm` visible from `wo`, which is what `sample_visible_normal`
+    // samples from
+    pub fn visible_d(&self, wo: Vec3, wm: Vec3) -> f64 {
+        let cos_o = wo.z.abs();
+        if cos_o == 0.0 {
+            return 0.0;
+        }
+        return self.g1(wo) * f64::max(wo.dot(&wm), 0.0) * self.d(wm) / cos_o;
+    }
+
+    // choose a microfacet normal visible from `wo` (which must be above the surface), with the
+    // method of Heitz, "Sampling the GGX Distribution of Visible Normals" (2018)
+    pub fn sample_visible_normal(&self, wo: Vec3, (u1, u2): (f64, f64)) -> Vec3 {
+        // stretch the view direction, so that the distribution becomes a hemisphere
+        let vh = Vec3::new(self.alpha * wo.x, self.alpha * wo.y, wo.z).unit_vector();
+        let len2 = vh.x * vh.x + vh.y * vh.y;
+        let t1 = match len2 > 0.0 {
+            true => Vec3::new(-vh.y, vh.x, 0.0) / len2.sqrt(),
+            false => Vec3::new(1.0, 0.0, 0.0),
+        };
+        let t2 = vh.cross(&t1);
+
+        // a point on the disk, squashed onto the part of the hemisphere that faces `vh`
+        let r = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z);
+        let p2 = (1.0 - s) * f64::sqrt(1.0 - p1 * p1) + s * r * phi.sin();
+        let nh = p1 * t1 + p2 * t2 + f64::sqrt(f64::max(1.0 - p1 * p1 - p2 * p2, 0.0)) * vh;
+
+        // unstretch
+        return Vec3::new(self.alpha * nh.x, self.alpha * nh.y, f64::max(nh.z, 1e-6)).unit_vector();
+    }
+}
+
+// the mirror image of `w` about the normal `n`, both pointing away from the surface
+pub fn reflect(w: Vec3, n: Vec3) -> Vec3 {
+    return 2.0 * w.dot(&n) * n - w;
+}
+
+// Schlick's approximation of the reflectance at an angle with cosine `cosine`, given the
+// reflectance `f0` at normal incidence
+pub fn fresnel_schlick(f0: Color, cosine: f64) -> Color {
+    let m = (1.0 - cosine.clamp(0.0, 1.0)).powi(5);
+    return f0 + (Color::new(1.0, 1.0, 1.0) - f0) * m;
+}
+
+// the reflectance at normal incidence of a dielectric with index of refraction `ior`, seen from
+// air
+pub fn dielectric_f0(ior: f64) -> f64 {
+    return ((ior - 1.0) / (ior + 1.0)).powi(2);
+}
+
+// the exact reflectance of unpolarised light, arriving from air at an angle with cosine
+// `cosine`, off a conductor with complex index of refraction `eta` + i `k`. each channel is
+// computed separately.
+pub fn fresnel_conductor(cosine: f64, eta: Color, k: Color) -> Color {
+    let channel = |eta: f64, k: f64| {
+        let cos2 = cosine.clamp(0.0, 1.0).powi(2);
+        let sin2 = 1.0 - cos2;
+        let t0 = eta * eta - k * k - sin2;
+        let a2_plus_b2 = f64::sqrt(t0 * t0 + 4.0 * eta * eta * k * k);
+        let a = f64::sqrt(f64::max(0.5 * (a2_plus_b2 + t0), 0.0));
+        let t1 = a2_plus_b2 + cos2;
+        let t2 = 2.0 * cosine.clamp(0.0, 1.0) * a;
+        let rs = (t1 - t2) / (t1 + t2);
+        let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+        let t4 = t2 * sin2;
+        let rp = rs * (t3 - t4) / (t3 + t4);
+        0.5 * (rp + rs)
+    };
+    return Color::new(
+        channel(eta.x, k.x),
+        channel(eta.y, k.y),
+        channel(eta.z, k.z),
+    );
+}
+
+// the exact reflectance of unpolarised light at a smooth boundary, arriving at an angle with
+// cosine `cosine` from the side the normal points to. `eta` is the index of refraction on the
+// far side of the boundary divided by that on the near side. a negative `cosine` means the
+// light arrives from the other side.
+pub fn fresnel_dielectric(cosine: f64, eta: f64) -> f64 {
+    let (cos_i, eta) = match cosine < 0.0 {
+        true => (f64::min(-cosine, 1.0), 1.0 / eta),
+        false => (f64::min(cosine, 1.0), eta),
+    };
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        // total internal reflection
+        return 1.0;
+    }
+    let cos_t = f64::sqrt(1.0 - sin2_t);
+    let r_parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    return 0.5 * (r_parallel * r_parallel + r_perpendicular * r_perpendicular);
+}
+
+// the direction in which light leaving along `w` arrived through a boundary with normal `n`
+// (on the same side as `w`), where `eta` is the index of refraction on the far side divided by
+// that on the near side. `None` if there's total internal reflection.
+pub fn refract(w: Vec3, n: Vec3, eta: f64) -> Option<Vec3> {
+    let cos_i = w.dot(&n);
+    let sin2_t = f64::max(1.0 - cos_i * cos_i, 0.0) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return None;
+    }
+    let cos_t = f64::sqrt(1.0 - sin2_t);
+    return Some(-w / eta + (cos_i / eta - cos_t) * n);
+}
@@ -0,0 +1,140 @@
+use crate::color::luminance;
+use crate::hit_record::HitRecord;
+use crate::material::{Material, ScatterRecord};
+use crate::ray::Ray;
+use crate::texture::Texture;
+use crate::vec3::{Color, Vec3};
+use rand::{Rng, RngCore};
+use std::sync::Arc;
+
+// a blend of several materials, such as rust over metal or dirt on glass, in proportions which
+// may vary over the surface. each ray is scattered by one of the materials, chosen at random in
+// proportion to its share at the hit point, and the blend reflects the weighted sum of what each
+// material would.
+//
+// every material but the first has a weight in [0, 1], given by a texture (the luminance of its
+// color, so a grayscale mask or a plain number). the first material makes up whatever the others
+// leave, and if their weights add up to more than 1, they are scaled down to share it.
+pub struct MixMaterial {
+    // at most `MAX_MATERIALS` of them
+    pub materials: Vec<Arc<dyn Material + Send + Sync>>,
+    // one fewer than there are materials, for the second material onwards
+    pub weights: Vec<Arc<dyn Texture>>,
+}
+
+impl MixMaterial {
+    // the most materials which can be blended, so that their shares fit in a fixed-size array
+    pub const MAX_MATERIALS: usize = 8;
+
+    // the share of each material at the hit point, adding up to 1. any after the last material
+    // are 0.
+    fn shares(&self, record: &HitRecord) -> [f64; Self::MAX_MATERIALS] {
+        let mut shares = [0.0; Self::MAX_MATERIALS];
+        for (share, weight) in shares[1..].iter_mut().zip(&self.weights) {
+            *share = luminance(weight.value(record.u, record.v, record.p)).clamp(0.0, 1.0);
+        }
+        let total: f64 = shares.iter().sum();
+        if total > 1.0 {
+            shares.iter_mut().for_each(|share| *share /= total);
+        } else {
+            shares[0] = 1.0 - total;
+        }
+        return shares;
+    }
+}
+
+impl Material for MixMaterial {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        gen: &mut dyn RngCore,
+    ) -> Option<ScatterRecord> {
+        let shares = self.shares(record);
+        let mut u = gen.gen::<f64>();
+        let mut chosen = self.materials.len() - 1;
+        for (i, share) in shares.iter().enumerate().take(self.materials.len()) {
+            if u < *share {
+                chosen = i;
+                break;
+            }
+            u -= share;
+        }
+
+        let scatter = self.materials[chosen].scatter(ray, record, gen)?;
+        // a direction which only the chosen material could pick (such as a mirror's) is
+        // weighted by its share as often as it's chosen, which cancels out. the same goes for a
+        // material with the whole share.
+        if scatter.pdf.is_none() || shares[chosen] >= 1.0 {
+            return Some(scatter);
+        }
+
+        // any of the materials could have picked the direction, so it's weighed against them
+        // all. the path keeps all its wavelengths unless one of those which could have picked
+        // it is dispersive.
+        let dir = scatter.ray.dir;
+        let (mut f, mut pdf, mut dispersive) = (Color::zeroes(), 0.0, scatter.dispersive);
+        for (material, share) in self.materials.iter().zip(shares) {
+            if share > 0.0 {
+                let material_pdf = material.pdf(ray, record, dir);
+                f += share * material.eval(ray, record, dir);
+                pdf += share * material_pdf;
+                dispersive |= material_pdf > 0.0 && material.is_dispersive();
+            }
+        }
+        if pdf <= 0.0 {
+            return None;
+        }
+        return Some(ScatterRecord {
+            attenuation: f / pdf,
+            pdf: Some(pdf),
+            ray: scatter.ray,
+            dispersive,
+        });
+    }
+
+    // one material may be mirror-like while another is rough
+    fn has_mixed_lobes(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, dir: Vec3) -> Color {
+        let mut sum = Color::zeroes();
+        for (material, share) in self.materials.iter().zip(self.shares(record)) {
+            if share > 0.0 {
+                sum += share * material.eval(ray, record, dir);
+            }
+        }
+        return sum;
+    }
+
+    fn pdf(&self, ray: &Ray, record: &HitRecord, dir: Vec3) -> f64 {
+        let mut sum = 0.0;
+        for (material, share) in self.materials.iter().zip(self.shares(record)) {
+            if share > 0.0 {
+                sum += share * material.pdf(ray, record, dir);
+            }
+        }
+        return sum;
+    }
+
+    fn emitted(&self, ray: &Ray, record: &HitRecord) -> Color {
+        let mut sum = Color::zeroes();
+        for (material, share) in self.materials.iter().zip(self.shares(record)) {
+            if share > 0.0 && material.is_emissive() {
+                sum += share * material.emitted(ray, record);
+            }
+        }
+        return sum;
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.materials
+            .iter()
+            .any(|material| material.is_dispersive())
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.materials.iter().any(|material| material.is_emissive())
+    }
+}
@@ -0,0 +1,56 @@
+use crate::aabb::Aabb;
+use crate::hit_record::HitRecord;
+use crate::hittable::Hittable;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::sphere::{hit_sphere, sphere_bounding_box};
+use crate::vec3::Point3;
+use rand::RngCore;
+use std::sync::Arc;
+
+// a sphere moving in a straight line, from `center0` at `time0` to `center1` at `time1`. it
+// rests at one end or the other outside of that interval.
+pub struct MovingSphere {
+    pub center0: Point3,
+    pub center1: Point3,
+    pub time0: f64,
+    pub time1: f64,
+    pub radius: f64,
+    pub mat_ptr: Arc<dyn Material + Send + Sync>,
+}
+
+impl MovingSphere {
+    pub fn center(&self, time: f64) -> Point3 {
+        let s = ((time - self.time0) / (self.time1 - self.time0)).clamp(0.0, 1.0);
+        return self.center0 + s * (self.center1 - self.center0);
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        record: &mut HitRecord,
+        _gen: &mut dyn RngCore,
+    ) -> bool {
+        let center = self.center(ray.time);
+        hit_sphere(
+            center,
+            self.radius,
+            &self.mat_ptr,
+            ray,
+            t_min,
+            t_max,
+            record,
+        )
+    }
+
+    // the box around the sphere's whole path
+    fn bounding_box(&self) -> Option<Aabb> {
+        let start = sphere_bounding_box(self.center0, self.radius);
+        let end = sphere_bounding_box(self.center1, self.radius);
+        Some(start.surrounding_box(&end))
+    }
+}
@@ -0,0 +1,35 @@
+use crate::perlin::Perlin;
+use crate::texture::Texture;
+use crate::vec3::{Color, Point3};
+
+#[derive(Debug, Copy, Clone)]
+pub enum NoiseStyle {
+    // plain Perlin noise
+    Smooth,
+    // the given number of octaves of noise
+    Turbulence(u32),
+    // bands along the z axis, distorted by turbulence of the given number of octaves
+    Marble(u32),
+}
+
+// `color` modulated by Perlin noise, evaluated in space at a frequency of `scale`
+pub struct NoiseTexture {
+    pub noise: Perlin,
+    pub scale: f64,
+    pub style: NoiseStyle,
+    pub color: Color,
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, p: Point3) -> Color {
+        let p = self.scale * p;
+        let t = match self.style {
+            NoiseStyle::Smooth => 0.5 * (1.0 + self.noise.noise(p)),
+            NoiseStyle::Turbulence(depth) => self.noise.turbulence(p, depth),
+            NoiseStyle::Marble(depth) => {
+                0.5 * (1.0 + f64::sin(p.z + 10.0 * self.noise.turbulence(p, depth)))
+            }
+        };
+        return t * self.color;
+    }
+}
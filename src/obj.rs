@@ -0,0 +1,307 @@
+// a loader for Wavefront OBJ meshes, along with their MTL material libraries.
+//
+// polygons are split into triangles, and per-vertex normals (`vn`) give smooth shading.
+// MTL materials are mapped onto the closest of our own materials:
+//   - anything with an emissive color (`Ke`) becomes a `DiffuseLight`
+//   - materials with the PBR extension's roughness (`Pr`) or metallic (`Pm`) become a
+//     `Principled` with base color `Kd`, or the image `map_Kd` if given
+//   - transparent materials (`d` < 1, `Tr` > 0, or a refractive `illum` model) become a
+//     `Dielectric`, with index of refraction `Ni`
+//   - reflective materials (a mirror `illum` model, or specular `Ks` brighter than diffuse `Kd`)
+//     become a `Metal` tinted by `Ks`, with roughness derived from the shininess `Ns`
+//   - everything else becomes a `Lambertian` with albedo `Kd`, or the image `map_Kd` if given
+
+use crate::dielectric::Dielectric;
+use crate::diffuse_light::DiffuseLight;
+use crate::image::WrapMode;
+use crate::image_io::{read_image, Encoding};
+use crate::image_texture::ImageTexture;
+use crate::lambertian::Lambertian;
+use crate::material::Material;
+use crate::mesh::{Face, Mesh};
+use crate::metal::Metal;
+use crate::principled::Principled;
+use crate::texture::{solid, Texture};
+use crate::vec3::{Color, Vec3};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+type ArcMaterial = Arc<dyn Material + Send + Sync>;
+
+// load the mesh at `path`. faces which don't name a material (or which name one that can't be
+// found) use `default_material`, and it is an error for there to be none.
+pub fn load_obj(path: &Path, default_material: Option<ArcMaterial>) -> Result<Mesh, String> {
+    let src = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+
+    let mut mesh = Mesh {
+        positions: Vec::new(),
+        normals: Vec::new(),
+        uvs: Vec::new(),
+        materials: Vec::new(),
+        faces: Vec::new(),
+    };
+    let default_index = default_material.map(|material| {
+        mesh.materials.push(material);
+        mesh.materials.len() - 1
+    });
+
+    // materials from the MTL libraries, by name, as indices into `mesh.materials`
+    let mut library: HashMap<String, usize> = HashMap::new();
+    let mut current_material = default_index;
+
+    for (i, line) in src.lines().enumerate() {
+        let err = |message: String| format!("{}:{}: {}", path.display(), i + 1, message);
+        let content = match line.find('#') {
+            Some(idx) => &line[..idx],
+            None => line,
+        };
+        let mut tokens = content.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => mesh.positions.push(parse_vec3(&args).map_err(err)?),
+            "vn" => mesh.normals.push(parse_vec3(&args).map_err(err)?),
+            "vt" => {
+                // the optional third (w) co-ordinate is ignored
+                let uv = parse_floats(&args, 1).map_err(err)?;
+                mesh.uvs.push((uv[0], *uv.get(1).unwrap_or(&0.0)));
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(err("a face needs at least three vertices".to_string()));
+                }
+                let material = match current_material {
+                    Some(material) => material,
+                    None => return Err(err("face has no material".to_string())),
+                };
+                let vertices = args
+                    .iter()
+                    .map(|arg| parse_face_vertex(arg, &mesh))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(err)?;
+                // split the polygon into a fan of triangles around the first vertex
+                for k in 1..vertices.len() - 1 {
+                    let [a, b, c] = [vertices[0], vertices[k], vertices[k + 1]];
+                    mesh.faces.push(Face {
+                        positions: [a.0, b.0, c.0],
+                        uvs: a.1.zip(b.1).zip(c.1).map(|((a, b), c)| [a, b, c]),
+                        normals: a.2.zip(b.2).zip(c.2).map(|((a, b), c)| [a, b, c]),
+                        material,
+                    });
+                }
+            }
+            "mtllib" => {
+                for name in args {
+                    for (name, material) in load_mtl(&base_dir.join(name))? {
+                        mesh.materials.push(material);
+                        library.insert(name, mesh.materials.len() - 1);
+                    }
+                }
+            }
+            "usemtl" => {
+                let name = args.first().copied().unwrap_or("");
+                current_material = library.get(name).copied().or(default_index);
+                if current_material.is_none() {
+                    return Err(err(format!("unknown material `{}`", name)));
+                }
+            }
+            // everything else (groups, smoothing groups, lines, ...) doesn't affect rendering
+            _ => {}
+        }
+    }
+    return Ok(mesh);
+}
+
+fn parse_floats(args: &[&str], min_count: usize) -> Result<Vec<f64>, String> {
+    if args.len() < min_count {
+        return Err(format!("expected at least {} numbers", min_count));
+    }
+    return args
+        .iter()
+        .map(|arg| match arg.parse::<f64>() {
+            Ok(x) if x.is_finite() => Ok(x),
+            _ => Err(format!("expected a number, found `{}`", arg)),
+        })
+        .collect();
+}
+
+fn parse_vec3(args: &[&str]) -> Result<Vec3, String> {
+    let xyz = parse_floats(args, 3)?;
+    return Ok(Vec3::new(xyz[0], xyz[1], xyz[2]));
+}
+
+// a face vertex is `v`, `v/vt`, `v//vn` or `v/vt/vn`, where each index counts from 1, or
+// backwards from the most recent element if negative. returns 0-based indices.
+fn parse_face_vertex(
+    arg: &str,
+    mesh: &Mesh,
+) -> Result<(usize, Option<usize>, Option<usize>), String> {
+    let resolve = |index: &str, len: usize| -> Result<usize, String> {
+        let resolved = match index.parse::<i64>() {
+            Ok(i) if i > 0 => i - 1,
+            Ok(i) if i < 0 => len as i64 + i,
+            _ => -1,
+        };
+        if resolved < 0 || resolved >= len as i64 {
+            return Err(format!(
+                "invalid index `{}` in face vertex `{}`",
+                index, arg
+            ));
+        }
+        return Ok(resolved as usize);
+    };
+
+    let parts: Vec<&str> = arg.split('/').collect();
+    if parts.len() > 3 {
+        return Err(format!("invalid face vertex `{}`", arg));
+    }
+    let position = resolve(parts[0], mesh.positions.len())?;
+    let uv = match parts.get(1) {
+        Some(index) if !index.is_empty() => Some(resolve(index, mesh.uvs.len())?),
+        _ => None,
+    };
+    let normal = match parts.get(2) {
+        Some(index) if !index.is_empty() => Some(resolve(index, mesh.normals.len())?),
+        _ => None,
+    };
+    return Ok((position, uv, normal));
+}
+
+// the subset of MTL parameters which we can map onto our materials
+struct MtlParams {
+    kd: Color,
+    map_kd: Option<Arc<dyn Texture>>,
+    ks: Color,
+    ke: Color,
+    ns: f64,
+    ni: f64,
+    dissolve: f64,
+    illum: u32,
+    roughness: Option<f64>,
+    metallic: Option<f64>,
+}
+
+impl Default for MtlParams {
+    fn default() -> Self {
+        Self {
+            kd: Color::new(0.8, 0.8, 0.8),
+            map_kd: None,
+            ks: Color::zeroes(),
+            ke: Color::zeroes(),
+            ns: 0.0,
+            ni: 1.5,
+            dissolve: 1.0,
+            illum: 2,
+            roughness: None,
+            metallic: None,
+        }
+    }
+}
+
+impl MtlParams {
+    fn into_material(self) -> ArcMaterial {
+        let max = |c: Color| c.x.max(c.y).max(c.z);
+        if max(self.ke) > 0.0 {
+            return Arc::new(DiffuseLight {
+                emit: solid(self.ke),
+            });
+        }
+        if self.roughness.is_some() || self.metallic.is_some() {
+            return Arc::new(Principled {
+                base_color: self.map_kd.unwrap_or_else(|| solid(self.kd)),
+                metallic: self.metallic.unwrap_or(0.0),
+                roughness: self.roughness.unwrap_or(0.5),
+                metallic_roughness: None,
+                ior: if self.ni >= 1.0 { self.ni } else { 1.5 },
+            });
+        }
+        if self.dissolve < 1.0 || matches!(self.illum, 4 | 6 | 7 | 9) {
+            let ir = if self.ni >= 1.0 { self.ni } else { 1.5 };
+            return Arc::new(Dielectric::new(ir));
+        }
+        if matches!(self.illum, 3 | 5 | 8) || max(self.ks) > max(self.kd) {
+            // a rough mapping from the Phong exponent onto fuzziness
+            let fuzz = f64::min(f64::sqrt(2.0 / (self.ns + 2.0)), 1.0);
+            return Arc::new(Metal {
+                albedo: solid(self.ks),
+                fuzz,
+            });
+        }
+        let albedo = self.map_kd.unwrap_or_else(|| solid(self.kd));
+        return Arc::new(Lambertian { albedo });
+    }
+}
+
+fn load_mtl(path: &Path) -> Result<Vec<(String, ArcMaterial)>, String> {
+    let src = std::fs::read_to_string(path)
+        .map_err(|err| format!("could not read `{}`: {}", path.display(), err))?;
+
+    let mut materials = Vec::new();
+    let mut current: Option<(String, MtlParams)> = None;
+    for (i, line) in src.lines().enumerate() {
+        let err = |message: String| format!("{}:{}: {}", path.display(), i + 1, message);
+        let content = match line.find('#') {
+            Some(idx) => &line[..idx],
+            None => line,
+        };
+        let mut tokens = content.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            if let Some((name, params)) = current.take() {
+                materials.push((name, params.into_material()));
+            }
+            let name = args
+                .first()
+                .ok_or_else(|| err("missing name".to_string()))?;
+            current = Some((name.to_string(), MtlParams::default()));
+            continue;
+        }
+        let params = match current.as_mut() {
+            Some((_, params)) => params,
+            None => return Err(err(format!("`{}` before any `newmtl`", keyword))),
+        };
+        match keyword {
+            "Kd" => params.kd = parse_vec3(&args).map_err(err)?,
+            "map_Kd" => {
+                // options such as `-s` (scale) come before the file name, and are ignored
+                let name = args
+                    .last()
+                    .ok_or_else(|| err("missing file name".to_string()))?;
+                let image_path = path.parent().unwrap_or(Path::new("")).join(name);
+                let image = read_image(&image_path, Encoding::Srgb).map_err(|e| {
+                    err(format!("could not read `{}`: {}", image_path.display(), e))
+                })?;
+                params.map_kd = Some(Arc::new(ImageTexture {
+                    image,
+                    wrap: WrapMode::Repeat,
+                }));
+            }
+            "Ks" => params.ks = parse_vec3(&args).map_err(err)?,
+            "Ke" => params.ke = parse_vec3(&args).map_err(err)?,
+            "Ns" => params.ns = parse_floats(&args, 1).map_err(err)?[0],
+            "Ni" => params.ni = parse_floats(&args, 1).map_err(err)?[0],
+            "d" => params.dissolve = parse_floats(&args, 1).map_err(err)?[0],
+            "Tr" => params.dissolve = 1.0 - parse_floats(&args, 1).map_err(err)?[0],
+            "Pr" => params.roughness = Some(parse_floats(&args, 1).map_err(err)?[0]),
+            "Pm" => params.metallic = Some(parse_floats(&args, 1).map_err(err)?[0]),
+            "illum" => params.illum = parse_floats(&args, 1).map_err(err)?[0] as u32,
+            // everything else (ambient color, texture maps, ...) is ignored
+            _ => {}
+        }
+    }
+    if let Some((name, params)) = current {
+        materials.push((name, params.into_material()));
+    }
+    return Ok(materials);
+}
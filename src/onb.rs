@@ -0,0 +1,32 @@
+use crate::vec3::Vec3;
+
+// an orthonormal basis, with `w` along a chosen direction and `u`, `v` perpendicular to it
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    // build a basis around the direction `n`, which needn't be normalised. uses the branchless
+    // construction from Duff et al., "Building an Orthonormal Basis, Revisited" (2017).
+    pub fn from_w(n: Vec3) -> Self {
+        let w = n.unit_vector();
+        let sign = f64::copysign(1.0, w.z);
+        let a = -1.0 / (sign + w.z);
+        let b = w.x * w.y * a;
+        let u = Vec3::new(1.0 + sign * w.x * w.x * a, sign * b, -sign * w.x);
+        let v = Vec3::new(b, sign + w.y * w.y * a, -w.y);
+        Self { u, v, w }
+    }
+
+    // a vector given in this basis, expressed in world co-ordinates
+    pub fn to_world(&self, local: Vec3) -> Vec3 {
+        local.x * self.u + local.y * self.v + local.z * self.w
+    }
+
+    // a world vector, expressed in this basis
+    pub fn to_local(&self, world: Vec3) -> Vec3 {
+        Vec3::new(world.dot(&self.u), world.dot(&self.v), world.dot(&self.w))
+    }
+}
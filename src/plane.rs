@@ -0,0 +1,51 @@
+use crate::aabb::Aabb;
+use crate::hit_record::HitRecord;
+use crate::hittable::Hittable;
+use crate::material::Material;
+use crate::onb::Onb;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use rand::RngCore;
+use std::sync::Arc;
+
+// an infinite plane through `point`. the front face is the side `normal` points towards.
+pub struct Plane {
+    pub point: Point3,
+    pub normal: Vec3,
+    pub mat_ptr: Arc<dyn Material + Send + Sync>,
+}
+
+impl Hittable for Plane {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        record: &mut HitRecord,
+        _gen: &mut dyn RngCore,
+    ) -> bool {
+        let denom = ray.dir.dot(&self.normal);
+        if denom.abs() < 1e-12 {
+            return false;
+        }
+        let t = (self.point - ray.origin).dot(&self.normal) / denom;
+        if t < t_min || t_max < t {
+            return false;
+        }
+
+        record.t = t;
+        record.p = ray.at(t);
+        // surface co-ordinates are distances along two directions in the plane
+        let basis = Onb::from_w(self.normal);
+        let offset = record.p - self.point;
+        record.u = offset.dot(&basis.u);
+        record.v = offset.dot(&basis.v);
+        record.mat_ptr = Some(Arc::clone(&self.mat_ptr));
+        record.set_face_normal(ray, self.normal.unit_vector());
+        return true;
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
+}
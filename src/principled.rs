@@ -0,0 +1,143 @@
+use crate::color::luminance;
+use crate::hit_record::HitRecord;
+use crate::material::{Material, ScatterRecord};
+use crate::microfacet::{dielectric_f0, fresnel_schlick, reflect, Ggx};
+use crate::onb::Onb;
+use crate::random::random_cosine_direction;
+use crate::ray::Ray;
+use crate::texture::Texture;
+use crate::vec3::{Color, Vec3};
+use rand::{Rng, RngCore};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+// the metallic-roughness material of glTF: a GGX specular layer over a diffuse base, which
+// blends into a pure GGX conductor tinted by `base_color` as `metallic` goes from 0 to 1.
+// the specular reflectance uses Schlick's approximation, starting from that of a dielectric
+// with index of refraction `ior` (4% for the default of 1.5) and rising to `base_color` for a
+// metal. the diffuse base receives the light which the specular layer doesn't reflect, as
+// estimated by the Fresnel reflectance towards the viewer, which (unlike glTF's weighting by the
+// reflectance of each microfacet) never adds up to more light than arrives.
+pub struct Principled {
+    pub base_color: Arc<dyn Texture>,
+    pub metallic: f64,
+    pub roughness: f64,
+    // a glTF metallic-roughness map, whose green channel scales `roughness` and blue channel
+    // scales `metallic`
+    pub metallic_roughness: Option<Arc<dyn Texture>>,
+    pub ior: f64,
+}
+
+// the material's parameters at a single point
+struct Lobes {
+    ggx: Ggx,
+    // the specular reflectance at normal incidence
+    f0: Color,
+    diffuse: Color,
+}
+
+impl Principled {
+    fn lobes(&self, record: &HitRecord) -> Lobes {
+        let base_color = self.base_color.value(record.u, record.v, record.p);
+        let (mut metallic, mut roughness) = (self.metallic, self.roughness);
+        if let Some(map) = &self.metallic_roughness {
+            let value = map.value(record.u, record.v, record.p);
+            roughness *= value.y;
+            metallic *= value.z;
+        }
+        let metallic = metallic.clamp(0.0, 1.0);
+        let dielectric = Color::new(1.0, 1.0, 1.0) * dielectric_f0(self.ior);
+        return Lobes {
+            ggx: Ggx::from_roughness(roughness.clamp(0.0, 1.0)),
+            f0: (1.0 - metallic) * dielectric + metallic * base_color,
+            diffuse: (1.0 - metallic) * base_color,
+        };
+    }
+
+    // the chance of sampling the specular lobe rather than the diffuse one, in proportion to
+    // the rough share of light each reflects
+    fn specular_probability(lobes: &Lobes, wo: Vec3) -> f64 {
+        let specular = luminance(fresnel_schlick(lobes.f0, wo.z));
+        let diffuse = luminance(lobes.diffuse) * (1.0 - specular);
+        if specular + diffuse <= 0.0 {
+            return 1.0;
+        }
+        return specular / (specular + diffuse);
+    }
+
+    fn eval_local(lobes: &Lobes, wo: Vec3, wi: Vec3) -> Color {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return Color::zeroes();
+        }
+        let wm = (wo + wi).unit_vector();
+        let fresnel = fresnel_schlick(lobes.f0, wo.dot(&wm));
+        let specular = lobes.ggx.d(wm) * lobes.ggx.g(wo, wi) / (4.0 * wo.z) * fresnel;
+        let transmitted = Color::new(1.0, 1.0, 1.0) - fresnel_schlick(lobes.f0, wo.z);
+        let diffuse = transmitted * lobes.diffuse * wi.z / PI;
+        return specular + diffuse;
+    }
+
+    fn pdf_local(lobes: &Lobes, wo: Vec3, wi: Vec3) -> f64 {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+        let wm = (wo + wi).unit_vector();
+        let p = Self::specular_probability(lobes, wo);
+        let specular = lobes.ggx.visible_d(wo, wm) / (4.0 * wo.dot(&wm));
+        let diffuse = wi.z / PI;
+        return p * specular + (1.0 - p) * diffuse;
+    }
+}
+
+impl Material for Principled {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        gen: &mut dyn RngCore,
+    ) -> Option<ScatterRecord> {
+        let lobes = self.lobes(record);
+        let onb = Onb::from_w(record.normal);
+        let wo = onb.to_local(-ray.dir.unit_vector());
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        let wi = if gen.gen::<f64>() < Self::specular_probability(&lobes, wo) {
+            let wm = lobes.ggx.sample_visible_normal(wo, (gen.gen(), gen.gen()));
+            reflect(wo, wm)
+        } else {
+            random_cosine_direction(gen)
+        };
+        let pdf = Self::pdf_local(&lobes, wo, wi);
+        if pdf <= 0.0 {
+            return None;
+        }
+
+        return Some(ScatterRecord {
+            ray: Ray {
+                origin: record.p,
+                dir: onb.to_world(wi),
+                time: ray.time,
+                wavelength: ray.wavelength,
+            },
+            attenuation: Self::eval_local(&lobes, wo, wi) / pdf,
+            pdf: Some(pdf),
+            dispersive: false,
+        });
+    }
+
+    fn eval(&self, ray: &Ray, record: &HitRecord, dir: Vec3) -> Color {
+        let onb = Onb::from_w(record.normal);
+        let wo = onb.to_local(-ray.dir.unit_vector());
+        let wi = onb.to_local(dir.unit_vector());
+        return Self::eval_local(&self.lobes(record), wo, wi);
+    }
+
+    fn pdf(&self, ray: &Ray, record: &HitRecord, dir: Vec3) -> f64 {
+        let onb = Onb::from_w(record.normal);
+        let wo = onb.to_local(-ray.dir.unit_vector());
+        let wi = onb.to_local(dir.unit_vector());
+        return Self::pdf_local(&self.lobes(record), wo, wi);
+    }
+}
@@ -1,45 +1,50 @@
 use crate::vec3::Vec3;
-use rand::prelude::ThreadRng;
+use crate::warp::{concentric_disk, cosine_hemisphere, uniform_sphere};
 use rand::Rng;
 
-pub fn _random_double(min: Option<f64>, max: Option<f64>, gen: &mut ThreadRng) -> f64 {
+pub fn _random_double<R: Rng + ?Sized>(min: Option<f64>, max: Option<f64>, gen: &mut R) -> f64 {
     let u = gen.gen::<f64>();
     let min = min.unwrap_or(0.0);
     let max = max.unwrap_or(1.0);
     min + (max - min) * u
 }
 
+// a pair of random numbers in [0, 1), for the functions in `warp`
+fn random_2d<R: Rng + ?Sized>(gen: &mut R) -> (f64, f64) {
+    let u1 = gen.gen::<f64>();
+    let u2 = gen.gen::<f64>();
+    return (u1, u2);
+}
+
 // sample a random vector in a unit-sphere, bounded in [-1, 1]
-pub fn random_in_unit_sphere(gen: &mut ThreadRng) -> Vec3 {
-    let mut u = [0f64; 3];
-    loop {
-        gen.fill(&mut u);
-        let vec = Vec3::new(u[0], u[1], u[2]);
-        if vec.length_squared() >= 1.0 {
-            continue;
-        }
-        return vec;
-    }
+pub fn random_in_unit_sphere<R: Rng + ?Sized>(gen: &mut R) -> Vec3 {
+    let dir = uniform_sphere(random_2d(gen));
+    // the cube root spreads the points evenly through the volume, rather than the radius
+    return gen.gen::<f64>().cbrt() * dir;
 }
 
 // sample a random vector from the surface of the unit sphere
-pub fn random_on_unit_sphere(gen: &mut ThreadRng) -> Vec3 {
-    return random_in_unit_sphere(gen).unit_vector();
+pub fn random_on_unit_sphere<R: Rng + ?Sized>(gen: &mut R) -> Vec3 {
+    return uniform_sphere(random_2d(gen));
 }
 
 // sample from inside a disk
-pub fn random_in_unit_disk(gen: &mut ThreadRng) -> Vec3 {
-    let mut u = [0f64; 2];
-    loop {
-        gen.fill(&mut u);
-        let vec = Vec3 {
-            x: u[0] * 2.0 - 1.0,
-            y: u[1] * 2.0 - 1.0,
-            z: 0.0,
-        };
-        if vec.length_squared() >= 1.0 {
-            continue;
-        }
-        return vec;
-    }
+pub fn random_in_unit_disk<R: Rng + ?Sized>(gen: &mut R) -> Vec3 {
+    let (x, y) = concentric_disk(random_2d(gen));
+    return Vec3::new(x, y, 0.0);
+}
+
+// sample a direction about +z, with density proportional to the cosine of its angle to +z
+pub fn random_cosine_direction<R: Rng + ?Sized>(gen: &mut R) -> Vec3 {
+    return cosine_hemisphere(random_2d(gen));
+}
+
+// sample a random direction within `acos(cos_max)` of the +z axis, uniformly by solid angle
+pub fn random_in_cone<R: Rng + ?Sized>(cos_max: f64, gen: &mut R) -> Vec3 {
+    let r1 = gen.gen::<f64>();
+    let r2 = gen.gen::<f64>();
+    let z = 1.0 + r2 * (cos_max - 1.0);
+    let phi = 2.0 * std::f64::consts::PI * r1;
+    let sin_theta = f64::sqrt(f64::max(1.0 - z * z, 0.0));
+    return Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, z);
 }
@@ -3,6 +3,11 @@ use crate::vec3::{Point3, Vec3};
 pub struct Ray {
     pub origin: Point3,
     pub dir: Vec3,
+    // the moment the ray was sent, within the camera's shutter interval
+    pub time: f64,
+    // in spectral rendering, the wavelength in nanometres which decides where the ray goes
+    // wherever that depends on the wavelength. `None` when rendering in RGB.
+    pub wavelength: Option<f64>,
 }
 
 impl Ray {
@@ -1,80 +1,481 @@
+use crate::color::{luminance, srgb_oetf};
 use crate::environment::Environment;
+use crate::film::{Film, Filter};
 use crate::hit_record::HitRecord;
 use crate::hittable::Hittable;
-use crate::hittable_list::HittableList;
 use crate::image::Image;
 use crate::material::Material;
 use crate::ray::Ray;
-use crate::vec3::Color;
-use rand::prelude::ThreadRng;
-use rand::Rng;
-use std::sync::Arc;
-
-pub fn render(
-    env: Arc<Environment>,
-    samples_per_pixel: u32,
-    image_width: u32,
-    max_depth: u32,
-) -> Image {
-    let image_height = ((image_width as f64) / env.camera.aspect_ratio) as u32;
+use crate::sampler::{Sampler, SamplerKind, SamplerRng};
+use crate::spectrum::SampledWavelengths;
+use crate::vec3::{Color, Point3, Vec3};
+use rand::{Rng, RngCore};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 
-    let mut gen = rand::thread_rng();
-
-    // println!("P3 {} {} 255", image_width, image_height);
-    let mut pixels: Vec<Vec<Color>> = Vec::new();
-    for j in (0..image_height).rev() {
-        let mut row: Vec<Color> = Vec::new();
-        for i in 0..image_width {
-            let mut pixel_color = Color::zeroes();
-            for _ in 0..samples_per_pixel {
-                let noise_u = gen.gen::<f64>();
-                let noise_v = gen.gen::<f64>();
-                let u: f64 = (i as f64 + noise_u) / (image_width as f64 - 1.0); // 0.0 to 1.0
-                let v: f64 = (j as f64 + noise_v) / (image_height as f64 - 1.0);
-                let ray = env.camera.get_ray(u, v, &mut gen);
-                pixel_color += ray_color(&ray, &env.world, max_depth, &mut gen);
+// image tiles are squares of this many pixels (smaller at the right and bottom edges)
+const TILE_SIZE: u32 = 16;
+
+// paths are never ended by Russian roulette before this many bounces
+const MIN_BOUNCES: u32 = 3;
+
+pub struct RenderSettings {
+    // the number of samples taken for every pixel, or the least number in adaptive mode
+    pub samples_per_pixel: u32,
+    pub image_width: u32,
+    pub max_depth: u32,
+    pub num_threads: u32,
+    // seeds all of the random sampling, so that renders with the same seed are identical
+    pub seed: u64,
+    pub sampler: SamplerKind,
+    // how samples are weighted into the pixels around them
+    pub filter: Filter,
+    // if set, pixels are sampled until they converge, rather than a fixed number of times
+    pub adaptive: Option<AdaptiveSettings>,
+    // trace paths carrying a few wavelengths of light each (see `spectrum`) rather than red,
+    // green and blue, so that materials can bend each wavelength a different way
+    pub spectral: bool,
+}
+
+pub struct AdaptiveSettings {
+    // a pixel has converged once the standard error of its mean brightness, after exposure and
+    // sRGB encoding (so on the 0-1 scale of the output image), falls below this
+    pub noise_threshold: f64,
+    // the exposure of the output image, in stops
+    pub exposure: f64,
+    // the most samples taken for any pixel, whether or not it has converged
+    pub max_samples: u32,
+}
+
+pub struct RenderOutput {
+    pub image: Image,
+    // the number of samples taken for each pixel, by row from the top
+    pub sample_counts: Vec<Vec<u32>>,
+}
+
+impl RenderOutput {
+    // the sample counts as a greyscale image, from black (no samples) to white (the most
+    // samples taken for any pixel)
+    pub fn sample_map(&self) -> Image {
+        let mut map = Image::zeroes(self.image.width, self.image.height);
+        let max = self
+            .sample_counts
+            .iter()
+            .flatten()
+            .copied()
+            .max()
+            .unwrap_or(1);
+        for (map_row, counts) in map.pixels.iter_mut().zip(&self.sample_counts) {
+            for (pixel, &count) in map_row.iter_mut().zip(counts) {
+                let level = count as f64 / max as f64;
+                *pixel = Color::new(level, level, level);
             }
-            row.push(pixel_color / (samples_per_pixel as f64));
         }
-        pixels.push(row);
+        return map;
+    }
+
+    // the mean number of samples taken per pixel
+    pub fn mean_samples(&self) -> f64 {
+        let total: u64 = self.sample_counts.iter().flatten().map(|&n| n as u64).sum();
+        let num_pixels = self.image.width as u64 * self.image.height as u64;
+        return total as f64 / u64::max(num_pixels, 1) as f64;
+    }
+}
+
+// running statistics of the samples taken for a pixel, updated with Welford's algorithm
+struct PixelStats {
+    count: u32,
+    // the factor by which the output's exposure scales each sample
+    exposure_scale: f64,
+    // the mean and sum of squared differences from the mean of the (sRGB encoded) luminance
+    mean: f64,
+    m2: f64,
+}
+
+impl PixelStats {
+    fn new(exposure: f64) -> Self {
+        Self {
+            count: 0,
+            exposure_scale: f64::powf(2.0, exposure),
+            mean: 0.0,
+            m2: 0.0,
+        }
+    }
+
+    fn add(&mut self, sample: Color) {
+        self.count += 1;
+        // the output's tone curve is left out, so that it can't hide the noise in bright pixels
+        // (as clamping would), and the sRGB curve carries on past 1 for the same reason
+        let x = srgb_oetf(f64::max(luminance(sample) * self.exposure_scale, 0.0));
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    // the standard error of the mean encoded luminance
+    fn standard_error(&self) -> f64 {
+        if self.count < 2 {
+            return f64::INFINITY;
+        }
+        let variance = self.m2 / (self.count - 1) as f64;
+        return f64::sqrt(variance / self.count as f64);
+    }
+}
+
+// the samples taken for a tile, and the number taken for each of its pixels
+type TileResult = (Film, Vec<Vec<u32>>);
+
+// a rectangle of pixels, in image coordinates (row 0 is the top of the image)
+struct Tile {
+    x0: u32,
+    y0: u32,
+    x1: u32,
+    y1: u32,
+}
+
+// render the image by splitting it into tiles, which are handed out to `num_threads` threads
+// through a shared counter. every pixel is sampled by exactly one thread, with exactly
+// `samples_per_pixel` samples (or, in adaptive mode, between that and `max_samples`), though the
+// filter may spread those samples over its neighbours too. the sampler derives every random
+// number from the seed, the pixel and the sample index, and the tiles' films are merged in a
+// fixed order, so the result does not depend on how tiles are scheduled.
+pub fn render(env: &Environment, settings: &RenderSettings) -> RenderOutput {
+    let image_width = settings.image_width;
+    let image_height = ((image_width as f64) / env.camera.aspect_ratio) as u32;
+
+    let mut tiles = Vec::new();
+    for y0 in (0..image_height).step_by(TILE_SIZE as usize) {
+        for x0 in (0..image_width).step_by(TILE_SIZE as usize) {
+            tiles.push(Tile {
+                x0,
+                y0,
+                x1: u32::min(x0 + TILE_SIZE, image_width),
+                y1: u32::min(y0 + TILE_SIZE, image_height),
+            });
+        }
     }
 
-    return Image {
-        width: image_width,
-        height: image_height,
-        pixels,
+    // the finished tiles, by index, with their samples and sample counts
+    let finished: Mutex<Vec<Option<TileResult>>> =
+        Mutex::new((0..tiles.len()).map(|_| None).collect());
+    let next_tile = AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        for _ in 0..settings.num_threads {
+            scope.spawn(|| loop {
+                let idx = next_tile.fetch_add(1, Ordering::Relaxed);
+                let tile = match tiles.get(idx) {
+                    Some(tile) => tile,
+                    None => break,
+                };
+                let mut sampler = settings
+                    .sampler
+                    .build(settings.seed, settings.samples_per_pixel);
+                let result = render_tile(env, settings, tile, image_height, &mut *sampler);
+                finished.lock().unwrap()[idx] = Some(result);
+            });
+        }
+    });
+
+    let mut film = Film::new(
+        0,
+        0,
+        image_width as usize,
+        image_height as usize,
+        settings.filter,
+    );
+    let mut sample_counts = vec![vec![0; image_width as usize]; image_height as usize];
+    let finished = finished.into_inner().unwrap();
+    for (tile, result) in tiles.iter().zip(finished) {
+        let (tile_film, counts) = result.expect("every tile is rendered");
+        film.merge(&tile_film);
+        for (row, count_row) in (tile.y0..tile.y1).zip(counts) {
+            let start = tile.x0 as usize;
+            sample_counts[row as usize][start..start + count_row.len()].copy_from_slice(&count_row);
+        }
+    }
+    return RenderOutput {
+        image: film.to_image(),
+        sample_counts,
     };
 }
 
-fn ray_color(ray: &Ray, world: &HittableList, depth: u32, gen: &mut ThreadRng) -> Color {
-    let mut record = HitRecord::dummy();
+// render the pixels of a single tile into a film covering the tile and the pixels around it,
+// along with the number of samples taken for each pixel of the tile, by row from the top
+fn render_tile(
+    env: &Environment,
+    settings: &RenderSettings,
+    tile: &Tile,
+    image_height: u32,
+    sampler: &mut dyn Sampler,
+) -> TileResult {
+    let image_width = settings.image_width;
+    let min_samples = settings.samples_per_pixel;
+    let max_samples = match &settings.adaptive {
+        Some(adaptive) => u32::max(adaptive.max_samples, min_samples),
+        None => min_samples,
+    };
 
-    // no more light gathered if depth exceeded
-    if depth == 0 {
-        return Color::zeroes();
+    let exposure = settings
+        .adaptive
+        .as_ref()
+        .map_or(0.0, |adaptive| adaptive.exposure);
+
+    let bounds = (tile.x0, tile.y0, tile.x1, tile.y1);
+    let mut film = Film::for_tile(bounds, image_width, image_height, settings.filter);
+    let mut counts: Vec<Vec<u32>> = Vec::new();
+    for row in tile.y0..tile.y1 {
+        // the camera's `t` co-ordinate increases upwards, whereas rows are counted downwards
+        let j = image_height - 1 - row;
+        let mut count_row: Vec<u32> = Vec::new();
+        for i in tile.x0..tile.x1 {
+            let mut stats = PixelStats::new(exposure);
+            while stats.count < max_samples {
+                sampler.start_pixel_sample(i, row, stats.count);
+                let (noise_u, noise_v) = sampler.get_2d();
+                let u: f64 = (i as f64 + noise_u) / (image_width as f64 - 1.0); // 0.0 to 1.0
+                let v: f64 = (j as f64 + noise_v) / (image_height as f64 - 1.0);
+                let mut ray = env.camera.get_ray(u, v, sampler);
+                let wavelengths = match settings.spectral {
+                    true => Some(SampledWavelengths::sample(sampler.get_1d())),
+                    false => None,
+                };
+                ray.wavelength = wavelengths.as_ref().map(|w| w.hero());
+                let mut gen = SamplerRng { sampler };
+                let color = ray_color(ray, env, settings.max_depth, wavelengths, &mut gen);
+                film.add_sample(i as f64 + noise_u, row as f64 + 1.0 - noise_v, color);
+                stats.add(color);
+
+                if let Some(adaptive) = &settings.adaptive {
+                    let converged = stats.standard_error() <= adaptive.noise_threshold;
+                    if stats.count >= min_samples && converged {
+                        break;
+                    }
+                }
+            }
+            count_row.push(stats.count);
+        }
+        counts.push(count_row);
     }
+    return (film, counts);
+}
+
+// the light arriving back along `ray`, found by following a path of bounces through the scene.
+// `throughput` is the fraction of the light found at the current vertex which makes it back
+// along the path to the camera. once past `MIN_BOUNCES`, paths are ended at random with a
+// probability which rises as the throughput falls, and the survivors are weighted up to make
+// up for those lost, so that little work is spent on paths which contribute little.
+// `max_depth` is only a safety cap on the length of a path.
+//
+// with `wavelengths`, the path carries those wavelengths of light rather than red, green and
+// blue, and every color met along the way is first turned into its values at those wavelengths.
+// the light found is converted back to RGB at the end.
+fn ray_color(
+    mut ray: Ray,
+    env: &Environment,
+    max_depth: u32,
+    mut wavelengths: Option<SampledWavelengths>,
+    gen: &mut dyn RngCore,
+) -> Color {
+    let mut color = Color::zeroes();
+    let mut throughput = Color::new(1.0, 1.0, 1.0);
+    // the density with which the material at the previous vertex chose the ray's direction, or
+    // `None` if it had no choice (for a mirror, or a ray from the camera). light which the ray
+    // finds by chance is weighted by it against the same light found by sampling the lights
+    // directly, so that each is counted once overall.
+    let mut prev_pdf: Option<f64> = None;
+
+    for bounce in 0..max_depth {
+        let mut record = HitRecord::dummy();
+        let mut hit = env.world.hit(&ray, 0.001, f64::INFINITY, &mut record, gen);
+
+        // the ray may be scattered by the atmosphere before it reaches the surface (or
+        // escapes). the scattering point is shaded like a point on an invisible surface.
+        if let Some(atmosphere) = &env.atmosphere {
+            let t_max = if hit { record.t } else { f64::INFINITY };
+            if let Some(t) = atmosphere.sample_scatter(&ray, 0.001, t_max, gen) {
+                record = HitRecord {
+                    p: ray.at(t),
+                    normal: -ray.dir.unit_vector(),
+                    t,
+                    u: 0.0,
+                    v: 0.0,
+                    front_face: true,
+                    mat_ptr: Some(Arc::clone(&atmosphere.phase_function)),
+                };
+                hit = true;
+            }
+        }
+
+        if !hit {
+            // the ray escapes and picks up the sky
+            let sky = env.sky(ray.dir);
+            if !is_black(sky) {
+                let sky = spectral(wavelengths.as_ref(), sky);
+                color += throughput * sky * mis_weight(&ray, env, prev_pdf, gen);
+            }
+            break;
+        }
 
-    // check for an intersection
-    if world.hit(&ray, 0.001, f64::INFINITY, &mut record) {
         // get the material pointer from the hit-record
         let mat_ptr: Arc<dyn Material + Send + Sync> = match &record.mat_ptr {
             Some(val) => Arc::clone(val),
             None => panic!(),
         };
 
-        // check for scattered ray on that material
-        return match mat_ptr.scatter(&ray, &record, gen) {
-            Some((reflected_ray, attenuation)) => {
-                attenuation * ray_color(&reflected_ray, &world, depth - 1, gen)
-            }
-            None => Color::zeroes(),
+        // light given off by the surface itself
+        let emitted = mat_ptr.emitted(&ray, &record);
+        if !is_black(emitted) {
+            let emitted = spectral(wavelengths.as_ref(), emitted);
+            color += throughput * emitted * mis_weight(&ray, env, prev_pdf, gen);
+        }
+
+        let scatter = match mat_ptr.scatter(&ray, &record, gen) {
+            Some(scatter) => scatter,
+            None => break,
         };
+
+        // the material sends each wavelength its own way, so only the one it was given (the
+        // hero wavelength) can be followed
+        if let Some(wavelengths) = &mut wavelengths {
+            if scatter.dispersive {
+                wavelengths.terminate_secondary();
+                throughput = Color::new(throughput.x, 0.0, 0.0);
+            }
+        }
+
+        // light reached through a sampled light counts as one more bounce, so is skipped when
+        // the material-sampled ray wouldn't be traced either. it's also skipped when the
+        // material chose a single direction, unless it scatters light in other directions too.
+        let has_lobes = scatter.pdf.is_some() || mat_ptr.has_mixed_lobes();
+        if has_lobes && bounce + 1 < max_depth && num_lights(env) > 0 {
+            let light = sample_light(&ray, &record, &*mat_ptr, env, wavelengths.as_ref(), gen);
+            color += throughput * light;
+        }
+
+        throughput *= spectral(wavelengths.as_ref(), scatter.attenuation);
+        if bounce + 1 >= MIN_BOUNCES {
+            let survival = f64::min(throughput.x.max(throughput.y).max(throughput.z), 0.95);
+            if gen.gen::<f64>() >= survival {
+                break;
+            }
+            throughput /= survival;
+        }
+        ray = scatter.ray;
+        prev_pdf = scatter.pdf;
+    }
+    return match &wavelengths {
+        Some(wavelengths) => wavelengths.to_rgb(color),
+        None => color,
+    };
+}
+
+// the light scattered back along `ray` by the material at the hit point, from a single light
+// sampled directly (next event estimation), weighted against sampling the material
+fn sample_light(
+    ray: &Ray,
+    record: &HitRecord,
+    material: &dyn Material,
+    env: &Environment,
+    wavelengths: Option<&SampledWavelengths>,
+    gen: &mut dyn RngCore,
+) -> Color {
+    let light_ray = Ray {
+        origin: record.p,
+        dir: random_light_direction(env, record.p, gen),
+        time: ray.time,
+        wavelength: ray.wavelength,
+    };
+    let f = material.eval(ray, record, light_ray.dir);
+    if is_black(f) {
+        return Color::zeroes();
+    }
+    let light_pdf = light_pdf(&light_ray, env, gen);
+    if light_pdf <= 0.0 {
+        return Color::zeroes();
+    }
+    let bsdf_pdf = material.pdf(ray, record, light_ray.dir);
+    let weight = power_heuristic(light_pdf, bsdf_pdf);
+    let light = direct_light(&light_ray, env, gen);
+    let (f, light) = (spectral(wavelengths, f), spectral(wavelengths, light));
+    return f * light * (weight / light_pdf);
+}
+
+// the light arriving along a ray towards a sampled light. this is whatever the ray reaches
+// first, which may be something other than the light, or nothing if the ray is scattered.
+fn direct_light(ray: &Ray, env: &Environment, gen: &mut dyn RngCore) -> Color {
+    let mut record = HitRecord::dummy();
+    let hit = env.world.hit(ray, 0.001, f64::INFINITY, &mut record, gen);
+    if let Some(atmosphere) = &env.atmosphere {
+        let t_max = if hit { record.t } else { f64::INFINITY };
+        if atmosphere.sample_scatter(ray, 0.001, t_max, gen).is_some() {
+            return Color::zeroes();
+        }
+    }
+    if !hit {
+        return env.sky(ray.dir);
+    }
+    return match &record.mat_ptr {
+        Some(mat_ptr) => mat_ptr.emitted(ray, &record),
+        None => Color::zeroes(),
+    };
+}
+
+// the weight given to light found by a ray sampled from a material with density `prev_pdf`
+fn mis_weight(ray: &Ray, env: &Environment, prev_pdf: Option<f64>, gen: &mut dyn RngCore) -> f64 {
+    match prev_pdf {
+        Some(bsdf_pdf) if num_lights(env) > 0 => {
+            power_heuristic(bsdf_pdf, light_pdf(ray, env, gen))
+        }
+        _ => 1.0,
+    }
+}
+
+// the number of lights which can be sampled: each emitter in `env.lights`, plus the sun
+fn num_lights(env: &Environment) -> usize {
+    env.lights.len() + env.sun.is_some() as usize
+}
+
+// a direction from `origin` towards one of the lights, chosen with equal probability
+fn random_light_direction(env: &Environment, origin: Point3, gen: &mut dyn RngCore) -> Vec3 {
+    if let Some(sun) = &env.sun {
+        if gen.gen_range(0..num_lights(env)) == 0 {
+            return sun.random(gen);
+        }
     }
+    return env.lights.random(origin, gen);
+}
+
+// the density with which `random_light_direction` chooses the ray's direction
+fn light_pdf(ray: &Ray, env: &Environment, gen: &mut dyn RngCore) -> f64 {
+    let mut sum = 0.0;
+    if !env.lights.is_empty() {
+        // the list's own density already averages over its lights
+        sum += env.lights.pdf_value(ray, gen) * env.lights.len() as f64;
+    }
+    if let Some(sun) = &env.sun {
+        sum += sun.pdf(ray.dir);
+    }
+    return sum / num_lights(env) as f64;
+}
+
+// Veach's power heuristic (with exponent 2), weighting a sample with density `pdf` against
+// one from another strategy with density `other_pdf`
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b == 0.0 {
+        return 0.0;
+    }
+    return a / (a + b);
+}
+
+// a color as carried by the path: its values at the path's wavelengths, if it has any
+fn spectral(wavelengths: Option<&SampledWavelengths>, color: Color) -> Color {
+    match wavelengths {
+        Some(wavelengths) => wavelengths.upsample(color),
+        None => color,
+    }
+}
 
-    // otherwise, shade background according to the y-component of the normalised ray direction
-    let unit_direction = ray.dir.unit_vector();
-    let t = 0.5 * (unit_direction.y + 1.0);
-    let white = Color::new(1.0, 1.0, 1.0);
-    let blue = Color::new(0.5, 0.7, 1.0);
-    (1.0 - t) * white + t * blue
+fn is_black(color: Color) -> bool {
+    color.x <= 0.0 && color.y <= 0.0 && color.z <= 0.0
 }
@@ -0,0 +1,551 @@
+// samplers decide where each sample of a pixel lands: its position within the pixel, the point
+// on the lens, the time, and every random choice along its path. they hand out numbers in
+// [0, 1) one dimension at a time. independent random numbers clump together and leave gaps,
+// while the other samplers spread each pixel's samples out more evenly, which reduces noise.
+//
+// every number is derived from the seed, the pixel, the sample index and the dimension, so a
+// render does not depend on how pixels are split between threads.
+
+use rand::RngCore;
+use std::sync::OnceLock;
+
+// the largest f64 below 1
+const ONE_MINUS_EPSILON: f64 = 1.0 - f64::EPSILON / 2.0;
+
+// the Halton sampler uses a different prime base for each of this many dimensions, after which
+// it falls back to independent random numbers
+const NUM_HALTON_DIMENSIONS: usize = 256;
+
+// the blue noise texture is a square tile of this many pixels across
+const BLUE_NOISE_SIZE: usize = 64;
+
+pub trait Sampler {
+    // start on sample `index` of the pixel in column `x` and row `y`, from the first dimension
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32);
+
+    // the next dimension of the current sample
+    fn get_1d(&mut self) -> f64;
+
+    // the next two dimensions, which are spread out well as pairs
+    fn get_2d(&mut self) -> (f64, f64);
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SamplerKind {
+    // independent uniform random numbers
+    Independent,
+    // one jittered sample in each of `samples_per_pixel` equal strata, shuffled independently for
+    // each dimension
+    Stratified,
+    // the Halton sequence, with its digits scrambled by a random permutation for each pixel
+    Halton,
+    // the first two dimensions of the Sobol sequence, Owen scrambled differently for each pixel
+    // and dimension
+    Sobol,
+    // the same points in every pixel, shifted by a blue noise texture, so that the error left
+    // over in each pixel looks like fine grain rather than blotches
+    BlueNoise,
+}
+
+impl SamplerKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "independent" => Some(SamplerKind::Independent),
+            "stratified" => Some(SamplerKind::Stratified),
+            "halton" => Some(SamplerKind::Halton),
+            "sobol" => Some(SamplerKind::Sobol),
+            "bluenoise" => Some(SamplerKind::BlueNoise),
+            _ => None,
+        }
+    }
+
+    // a sampler for renders which take (at least) `samples_per_pixel` samples of each pixel.
+    // samplers which are stratified are at their best when this is a power of two.
+    pub fn build(self, seed: u64, samples_per_pixel: u32) -> Box<dyn Sampler> {
+        let state = SampleState {
+            seed,
+            x: 0,
+            y: 0,
+            index: 0,
+            dim: 0,
+        };
+        let block_size = samples_per_pixel.next_power_of_two();
+        match self {
+            SamplerKind::Independent => Box::new(IndependentSampler { state }),
+            SamplerKind::Stratified => {
+                // the most square grid of cells for 2D samples
+                let mut nx = (samples_per_pixel as f64).sqrt() as u32;
+                while !samples_per_pixel.is_multiple_of(nx) {
+                    nx -= 1;
+                }
+                Box::new(StratifiedSampler {
+                    state,
+                    samples: samples_per_pixel,
+                    nx,
+                    ny: samples_per_pixel / nx,
+                })
+            }
+            SamplerKind::Halton => Box::new(HaltonSampler {
+                state,
+                primes: first_primes(NUM_HALTON_DIMENSIONS),
+            }),
+            SamplerKind::Sobol => Box::new(SobolSampler { state, block_size }),
+            SamplerKind::BlueNoise => Box::new(BlueNoiseSampler {
+                state,
+                block_size,
+                texture: blue_noise(),
+            }),
+        }
+    }
+}
+
+// lets the rest of a path draw its random numbers from a sampler, one dimension per number
+pub struct SamplerRng<'a> {
+    pub sampler: &'a mut dyn Sampler,
+}
+
+impl RngCore for SamplerRng<'_> {
+    fn next_u32(&mut self) -> u32 {
+        (self.sampler.get_1d() * 4294967296.0) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        // `gen::<f64>()` keeps the top 53 bits, so those carry the sample exactly
+        ((self.sampler.get_1d() * (1u64 << 53) as f64) as u64) << 11
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        return Ok(());
+    }
+}
+
+// the sample being taken, shared by every sampler
+struct SampleState {
+    seed: u64,
+    x: u32,
+    y: u32,
+    index: u32,
+    dim: u32,
+}
+
+impl SampleState {
+    fn start(&mut self, x: u32, y: u32, index: u32) {
+        (self.x, self.y, self.index, self.dim) = (x, y, index, 0);
+    }
+
+    // a hash of the pixel and the current dimension, the same for every sample of the pixel
+    fn dim_hash(&self) -> u64 {
+        hash(&[self.seed, self.x as u64, self.y as u64, self.dim as u64])
+    }
+
+    // a hash which also differs between samples
+    fn sample_hash(&self) -> u64 {
+        mix_bits(self.dim_hash() ^ self.index as u64)
+    }
+}
+
+struct IndependentSampler {
+    state: SampleState,
+}
+
+impl Sampler for IndependentSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.state.start(x, y, index);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let u = to_unit(self.state.sample_hash());
+        self.state.dim += 1;
+        return u;
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.get_1d(), self.get_1d())
+    }
+}
+
+struct StratifiedSampler {
+    state: SampleState,
+    samples: u32,
+    // 2D samples are placed in a grid of `nx` by `ny` cells
+    nx: u32,
+    ny: u32,
+}
+
+impl StratifiedSampler {
+    // the stratum of the current sample, out of `n`. samples beyond the first `n` start a new
+    // round, which is shuffled differently.
+    fn stratum(&self, n: u32) -> u32 {
+        let (round, i) = (self.state.index / n, self.state.index % n);
+        let seed = mix_bits(self.state.dim_hash() ^ round as u64);
+        return permutation_element(i, n, seed as u32);
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.state.start(x, y, index);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let stratum = self.stratum(self.samples);
+        let jitter = to_unit(self.state.sample_hash());
+        self.state.dim += 1;
+        return f64::min(
+            (stratum as f64 + jitter) / self.samples as f64,
+            ONE_MINUS_EPSILON,
+        );
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let cell = self.stratum(self.nx * self.ny);
+        let (cx, cy) = (cell % self.nx, cell / self.nx);
+        let h = self.state.sample_hash();
+        let (jx, jy) = (to_unit(h), to_unit(mix_bits(h)));
+        self.state.dim += 2;
+        let x = (cx as f64 + jx) / self.nx as f64;
+        let y = (cy as f64 + jy) / self.ny as f64;
+        return (
+            f64::min(x, ONE_MINUS_EPSILON),
+            f64::min(y, ONE_MINUS_EPSILON),
+        );
+    }
+}
+
+struct HaltonSampler {
+    state: SampleState,
+    primes: Vec<u64>,
+}
+
+impl Sampler for HaltonSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.state.start(x, y, index);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let u = match self.primes.get(self.state.dim as usize) {
+            Some(&base) => {
+                let index = self.state.index as u64;
+                owen_scrambled_radical_inverse(index, base, self.state.dim_hash())
+            }
+            None => to_unit(self.state.sample_hash()),
+        };
+        self.state.dim += 1;
+        return u;
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.get_1d(), self.get_1d())
+    }
+}
+
+struct SobolSampler {
+    state: SampleState,
+    // sample indices are shuffled within aligned blocks of this (power of two) size. see
+    // `shuffle`.
+    block_size: u32,
+}
+
+impl Sampler for SobolSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.state.start(x, y, index);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let h = self.state.dim_hash();
+        let index = shuffle(self.state.index, self.block_size, h as u32);
+        self.state.dim += 1;
+        return to_unit32(fast_owen_scramble(index.reverse_bits(), (h >> 32) as u32));
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let h = self.state.dim_hash();
+        let index = shuffle(self.state.index, self.block_size, h as u32);
+        let h2 = mix_bits(h);
+        self.state.dim += 2;
+        return (
+            to_unit32(fast_owen_scramble(index.reverse_bits(), (h >> 32) as u32)),
+            to_unit32(fast_owen_scramble(sobol_dim1(index), (h2 >> 32) as u32)),
+        );
+    }
+}
+
+struct BlueNoiseSampler {
+    state: SampleState,
+    block_size: u32,
+    texture: &'static [f64],
+}
+
+impl BlueNoiseSampler {
+    // a hash of the current dimension alone, so that every pixel gets the same points
+    fn dim_hash(&self) -> u64 {
+        hash(&[self.state.seed, self.state.dim as u64])
+    }
+
+    // the blue noise texture, looked up at this pixel with an offset chosen by `h`
+    fn offset(&self, h: u64) -> f64 {
+        let n = BLUE_NOISE_SIZE;
+        let x = (self.state.x as usize + (h as usize % n)) % n;
+        let y = (self.state.y as usize + ((h >> 16) as usize % n)) % n;
+        return self.texture[y * n + x];
+    }
+}
+
+impl Sampler for BlueNoiseSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, index: u32) {
+        self.state.start(x, y, index);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let h = self.dim_hash();
+        let index = shuffle(self.state.index, self.block_size, h as u32);
+        let u = to_unit32(index.reverse_bits()) + self.offset(h >> 32);
+        self.state.dim += 1;
+        return f64::min(u.fract(), ONE_MINUS_EPSILON);
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let h = self.dim_hash();
+        let index = shuffle(self.state.index, self.block_size, h as u32);
+        let u = to_unit32(index.reverse_bits()) + self.offset(h >> 32);
+        let v = to_unit32(sobol_dim1(index)) + self.offset(mix_bits(h));
+        self.state.dim += 2;
+        return (
+            f64::min(u.fract(), ONE_MINUS_EPSILON),
+            f64::min(v.fract(), ONE_MINUS_EPSILON),
+        );
+    }
+}
+
+// shuffle the sample indices within each aligned block of `block_size`, a power of two. each block
+// of a Sobol sequence is evenly spread on its own, so this keeps the points of each dimension the
+// same while pairing them up randomly between dimensions (using the same order for every
+// dimension would line the points of any two dimensions up along a curve).
+fn shuffle(index: u32, block_size: u32, seed: u32) -> u32 {
+    let (block, i) = (index / block_size, index % block_size);
+    let seed = mix_bits(seed as u64 ^ block as u64) as u32;
+    return block * block_size + permutation_element(i, block_size, seed);
+}
+
+// the 64-bit finaliser from MurmurHash3, which spreads every bit of the input over the output
+fn mix_bits(mut v: u64) -> u64 {
+    v ^= v >> 31;
+    v = v.wrapping_mul(0x7fb5d329728ea185);
+    v ^= v >> 27;
+    v = v.wrapping_mul(0x81dadef4bc2dd44d);
+    v ^= v >> 33;
+    return v;
+}
+
+fn hash(values: &[u64]) -> u64 {
+    values.iter().fold(0x9e3779b97f4a7c15, |h, &v| {
+        mix_bits(h ^ v.wrapping_mul(0xbf58476d1ce4e5b9))
+    })
+}
+
+// a number in [0, 1) from the top 53 bits of `h`
+fn to_unit(h: u64) -> f64 {
+    (h >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+// a number in [0, 1) from a 32-bit fixed point fraction
+fn to_unit32(v: u32) -> f64 {
+    v as f64 * (1.0 / 4294967296.0)
+}
+
+// element `i` of a random permutation of 0..n chosen by `seed`, computed without building the
+// permutation. from Kensler, "Correlated Multi-Jittered Sampling" (2013).
+fn permutation_element(mut i: u32, n: u32, seed: u32) -> u32 {
+    let mut w = n - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    // permute within the next power of two, repeating until the result falls below `n`
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= seed >> 16;
+        i ^= (i & w) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= seed >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < n {
+            break;
+        }
+    }
+    return ((i as u64 + seed as u64) % n as u64) as u32;
+}
+
+// the radical inverse of `index` in `base` (its digits mirrored about the point), where each
+// digit is permuted according to the digits before it. this is Owen scrambling, which keeps the
+// sequence's stratification while making it random.
+fn owen_scrambled_radical_inverse(mut index: u64, base: u64, seed: u64) -> f64 {
+    let inv_base = 1.0 / base as f64;
+    let mut inv_base_m = 1.0;
+    let mut reversed: u64 = 0;
+    // carry on past the last non-zero digit until the digits no longer affect an f64, since
+    // the (permuted) zeros beyond it matter too
+    while 1.0 - inv_base_m < 1.0 {
+        let next = index / base;
+        let digit = index - next * base;
+        let digit_seed = mix_bits(seed ^ reversed) as u32;
+        let digit = permutation_element(digit as u32, base as u32, digit_seed) as u64;
+        reversed = reversed * base + digit;
+        inv_base_m *= inv_base;
+        index = next;
+    }
+    return f64::min(reversed as f64 * inv_base_m, ONE_MINUS_EPSILON);
+}
+
+// Owen scrambling of a 32-bit fixed point fraction, by the hash-based method of Laine and
+// Karras (2011), as refined by Burley (2020)
+fn fast_owen_scramble(v: u32, seed: u32) -> u32 {
+    let mut v = v.reverse_bits();
+    v ^= v.wrapping_mul(0x3d20adea);
+    v = v.wrapping_add(seed);
+    v = v.wrapping_mul((seed >> 16) | 1);
+    v ^= v.wrapping_mul(0x05526c56);
+    v ^= v.wrapping_mul(0x53a22864);
+    return v.reverse_bits();
+}
+
+// the second dimension of the Sobol sequence, as a 32-bit fixed point fraction. its generator
+// matrix is Pascal's triangle mod 2, each column of which is the one before xor'd with itself
+// shifted down by one.
+fn sobol_dim1(mut index: u32) -> u32 {
+    let mut v = 1u32 << 31;
+    let mut result = 0;
+    while index != 0 {
+        if index & 1 != 0 {
+            result ^= v;
+        }
+        index >>= 1;
+        v ^= v >> 1;
+    }
+    return result;
+}
+
+fn first_primes(n: usize) -> Vec<u64> {
+    let mut primes: Vec<u64> = Vec::with_capacity(n);
+    let mut candidate = 2;
+    while primes.len() < n {
+        if primes.iter().all(|p| candidate % p != 0) {
+            primes.push(candidate);
+        }
+        candidate += 1;
+    }
+    return primes;
+}
+
+// a tile of blue noise (values in [0, 1) with no low frequencies, so that neighbouring pixels
+// differ as much as possible), made once and shared by every render
+fn blue_noise() -> &'static [f64] {
+    static TEXTURE: OnceLock<Vec<f64>> = OnceLock::new();
+    return TEXTURE.get_or_init(void_and_cluster);
+}
+
+// Ulichney's void-and-cluster method (1993). pixels are switched on one at a time, each in the
+// largest gap left between those already on, and the order in which they are switched on gives
+// their value. gaps and clusters are found with a gaussian "energy" around each pixel which is
+// on, wrapping around the edges so that the tile repeats seamlessly.
+fn void_and_cluster() -> Vec<f64> {
+    let n = BLUE_NOISE_SIZE;
+    let count = n * n;
+
+    // the energy contributed by a pixel to another, by their (wrapped) offset
+    let sigma: f64 = 1.5;
+    let mut kernel = vec![0.0; count];
+    for dy in 0..n {
+        for dx in 0..n {
+            let (wx, wy) = (dx.min(n - dx) as f64, dy.min(n - dy) as f64);
+            kernel[dy * n + dx] = f64::exp(-(wx * wx + wy * wy) / (2.0 * sigma * sigma));
+        }
+    }
+    let toggle = |pattern: &mut Vec<bool>, energy: &mut Vec<f64>, p: usize| {
+        pattern[p] = !pattern[p];
+        let sign = if pattern[p] { 1.0 } else { -1.0 };
+        let (px, py) = (p % n, p / n);
+        for qy in 0..n {
+            for qx in 0..n {
+                let (dx, dy) = ((qx + n - px) % n, (qy + n - py) % n);
+                energy[qy * n + qx] += sign * kernel[dy * n + dx];
+            }
+        }
+    };
+    // the pixel which is on in the densest cluster, or off in the largest void
+    let tightest_cluster = |pattern: &[bool], energy: &[f64]| {
+        (0..count)
+            .filter(|&p| pattern[p])
+            .max_by(|&a, &b| energy[a].total_cmp(&energy[b]))
+            .unwrap()
+    };
+    let largest_void = |pattern: &[bool], energy: &[f64]| {
+        (0..count)
+            .filter(|&p| !pattern[p])
+            .min_by(|&a, &b| energy[a].total_cmp(&energy[b]))
+            .unwrap()
+    };
+
+    // start from a tenth of the pixels switched on at random, then even them out by moving the
+    // tightest cluster into the largest void until that changes nothing
+    let mut pattern = vec![false; count];
+    let mut energy = vec![0.0; count];
+    let mut k = 0;
+    while pattern.iter().filter(|&&on| on).count() < count / 10 {
+        let p = (hash(&[k]) % count as u64) as usize;
+        if !pattern[p] {
+            toggle(&mut pattern, &mut energy, p);
+        }
+        k += 1;
+    }
+    loop {
+        let cluster = tightest_cluster(&pattern, &energy);
+        toggle(&mut pattern, &mut energy, cluster);
+        let void = largest_void(&pattern, &energy);
+        toggle(&mut pattern, &mut energy, void);
+        if void == cluster {
+            break;
+        }
+    }
+    let num_initial = count / 10;
+
+    // the initial pixels are ranked by removing the tightest cluster each time, and the rest by
+    // filling the largest void each time
+    let mut ranks = vec![0; count];
+    let (mut removing, mut removing_energy) = (pattern.clone(), energy.clone());
+    for rank in (0..num_initial).rev() {
+        let cluster = tightest_cluster(&removing, &removing_energy);
+        toggle(&mut removing, &mut removing_energy, cluster);
+        ranks[cluster] = rank;
+    }
+    for rank in num_initial..count {
+        let void = largest_void(&pattern, &energy);
+        toggle(&mut pattern, &mut energy, void);
+        ranks[void] = rank;
+    }
+    return ranks
+        .iter()
+        .map(|&rank| (rank as f64 + 0.5) / count as f64)
+        .collect();
+}
@@ -0,0 +1,1285 @@
+// a small line-based scene description format. each non-empty line holds one directive,
+// followed by whitespace-separated `key=value` fields. vectors are written as `x,y,z`, and
+// `#` starts a comment. for example:
+//
+//     camera look_from=13,2,3 look_at=0,0,0 vfov=20 aperture=0.1 focus_dist=10
+//     texture checks checker even=0.2,0.3,0.1 odd=0.9,0.9,0.9 scale=0.5
+//     material ground lambertian albedo=checks
+//     material glass dielectric ir=1.5
+//     material brass principled base_color=0.9,0.7,0.3 metallic=1 roughness=0.3
+//     material gold conductor metal=gold roughness=0.2
+//     material paint coated base=brass roughness=0.05
+//     material rusty mix materials=gold,ground weights=0.3
+//     material lamp diffuse_light emit=4,4,4
+//     plane point=0,0,0 normal=0,1,0 material=ground
+//     sphere center=0,1,0 radius=1 material=glass
+//     moving_sphere center0=2,1,0 center1=2,1.5,0 radius=0.5 material=ground
+//     triangle v0=-1,0,-1 v1=1,0,-1 v2=0,1,-1 material=ground
+//     xz_rect x0=-1 x1=1 z0=-1 z1=1 y=3 material=lamp flip=true
+//     box min=2,0,-1 max=3,1,0 material=ground
+//     disk center=0,0.01,0 normal=0,1,0 radius=2 material=ground
+//     cylinder base=-3,0,0 top=-3,2,0 radius=0.5 material=ground
+//     cone base=3,0,2 top=3,1.5,2 radius=0.5 capped=false material=ground
+//     mesh path=teapot.obj material=glass
+//     object pillar cylinder base=0,0,0 top=0,3,0 radius=0.2 material=ground
+//     instance object=pillar translate=-2,0,-2
+//     instance object=pillar scale=0.5 rotate_z=90 translate=2,0.1,-2
+//     background gradient bottom=1,1,1 top=0.5,0.7,1
+//
+// materials must be named and defined before the objects which use them. camera fields
+// which are left out take their values from `CameraSettings::default()`. the background is one
+// of `solid color=...`, `gradient bottom=... top=...` or `envmap path=... [intensity=...]
+// [rotation=...]`, where `path` is an image (.hdr, .png or .ppm) relative to the scene file.
+//
+// a `principled` material follows glTF's metallic-roughness model, with `base_color`, optional
+// `metallic` (0 by default) and `roughness` (0.5 by default) in [0, 1], an optional
+// `metallic_roughness` texture whose green and blue channels scale those two, and an optional
+// `ior` (1.5 by default) for the specular reflection of non-metals. a `conductor` is a rough
+// metal given either by name, `metal=gold|silver|copper|aluminium`, or by its complex index of
+// refraction `eta=... k=...`, with an optional `roughness` (0 by default).
+//
+// a `dielectric` can be given a `roughness` (0 by default), the index of refraction
+// `exterior_ir` of whatever surrounds it (1 by default, for air) and an `absorption` per unit
+// distance travelled inside it, in each channel. in place of `ir`, it can be given a dispersion,
+// which splits light into its colors in a `--spectral` render: either one of the glasses
+// `glass=bk7|sf11|fused_silica|diamond`, Cauchy's equation `cauchy_a=... cauchy_b=...` or
+// Sellmeier's `sellmeier_b=b1,b2,b3 sellmeier_c=c1,c2,c3`, with wavelengths in micrometres. a
+// dispersion must give an index of refraction of at least 1 across the visible spectrum.
+// a `thin_dielectric ir=... [thickness=...]` is a sheet with no thickness, such as a window.
+// given a `thickness` in nanometres, it is instead a film like that of a soap bubble, colored by
+// interference.
+//
+// a `coated` material is a clear coat, like varnish or lacquer, over the material named by
+// `base`, which must be defined beforehand. the coat has an index of refraction `ior` (1.5 by
+// default), a `roughness` (0 by default) and a `tint` (white by default), the color or texture
+// of light which passes through the coat to the base and back out.
+//
+// a `mix` blends the materials named by `materials=a,b,...`, each scattering a share of the light
+// at every point. every material after the first is given a weight in [0, 1] by
+// `weights=w1,w2,...`, each of which is either a number or the name of a texture (such as a
+// grayscale mask, whose luminance is used). the first material fills whatever share is left. by
+// default, every material has an equal share.
+//
+// wherever a material takes a color, it can instead be given the name of a texture, defined
+// beforehand with `texture <name> <kind> [fields...]`. the kinds are:
+//   - `solid color=...`
+//   - `checker even=... odd=... [scale=...]`, a 3D checkerboard of cubes `scale` wide, where
+//     `even` and `odd` are themselves colors or textures
+//   - `image path=... [wrap=repeat|clamp|mirror] [linear=true]`, an image laid over the
+//     surface's (u, v) co-ordinates. PNG and PPM images are taken to be sRGB encoded colors,
+//     unless `linear=true` marks them as holding plain numbers (such as a metallic-roughness map).
+//     HDR images always hold linear values, so `linear` makes no difference to them.
+//   - `noise [scale=...] [style=smooth|turbulence|marble] [depth=...] [color=...] [seed=...]`,
+//     Perlin noise, where `depth` is the number of octaves of turbulence
+//
+// rectangles lie in an axis-aligned plane, and give their extent along the two axes in the plane
+// along with their position on the third. cylinders and cones are capped unless `capped=false`,
+// and a cone's `top` is its apex. any object can be given `flip=true` to swap its front and back
+// faces, for instance so that a one-sided light faces the other way. a moving sphere travels
+// from `center0` at `time0` to `center1` at `time1` (by default 0 and 1), and is blurred when
+// the camera's `shutter_open` and `shutter_close` span part of that journey.
+// meshes are Wavefront OBJ files, again relative to the scene file. their `material` is
+// optional, and is used for any faces without a material of their own from the OBJ file.
+//
+// objects can also be moved with `scale=...` (one factor, or `x,y,z`), `rotate_x=...`,
+// `rotate_y=...`, `rotate_z=...` (in degrees) and `translate=x,y,z`, applied in that order.
+// `object <name> <directive> [fields...]` adds to a named group of objects which isn't drawn
+// itself, but is shared by every `instance object=<name>`, each with its own transform.
+//
+// `medium object=<name> density=... albedo=...` fills a named (convex) object with fog or smoke,
+// which scatters light of the color or texture `albedo` evenly in all directions. the material
+// of the object itself is unused. `atmosphere density=... [albedo=...] [center=...]
+// [radius=...]` fills a ball (by default of radius 1000 around the origin) with a haze which
+// surrounds the whole scene.
+//
+// `sun direction=... color=... [angle=...]` adds a distant disk of light, `angle` degrees across
+// (0.53 by default, like the real sun), in `direction`. `color` is the light it casts on a
+// surface facing it, whatever its size. the sun, and any sphere, rectangle, box, disk, triangle
+// or mesh made of an emissive material, is sampled directly when shading, which is far less
+// noisy than waiting for rays to find it. emitters inside instances are not sampled this way.
+
+use crate::aabox::AaBox;
+use crate::aarect::AaRect;
+use crate::atmosphere::Atmosphere;
+use crate::background::{Background, EnvironmentMap, Sun};
+use crate::camera::CameraSettings;
+use crate::coated::Coated;
+use crate::conductor::{metal_ior, Conductor};
+use crate::cone::Cone;
+use crate::constant_medium::ConstantMedium;
+use crate::cylinder::Cylinder;
+use crate::dielectric::{Dielectric, Dispersion};
+use crate::diffuse_light::DiffuseLight;
+use crate::disk::Disk;
+use crate::environment::Environment;
+use crate::flip_face::FlipFace;
+use crate::hittable::Hittable;
+use crate::hittable_list::HittableList;
+use crate::image::WrapMode;
+use crate::image_io::{read_image, Encoding};
+use crate::image_texture::ImageTexture;
+use crate::instance::Instance;
+use crate::isotropic::Isotropic;
+use crate::lambertian::Lambertian;
+use crate::mat4::Mat4;
+use crate::material::Material;
+use crate::mesh::Mesh;
+use crate::metal::Metal;
+use crate::mix_material::MixMaterial;
+use crate::moving_sphere::MovingSphere;
+use crate::noise_texture::{NoiseStyle, NoiseTexture};
+use crate::obj::load_obj;
+use crate::perlin::Perlin;
+use crate::plane::Plane;
+use crate::principled::Principled;
+use crate::spectrum::{MAX_WAVELENGTH, MIN_WAVELENGTH};
+use crate::sphere::Sphere;
+use crate::texture::{solid, Checker, Texture};
+use crate::thin_dielectric::ThinDielectric;
+use crate::triangle::Triangle;
+use crate::vec3::{Color, Point3, Vec3};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+type ArcMaterial = Arc<dyn Material + Send + Sync>;
+type ArcTexture = Arc<dyn Texture>;
+
+// a parsed scene, prior to building the camera and acceleration structure
+pub struct Scene {
+    pub camera: CameraSettings,
+    pub world: HittableList,
+    pub background: Background,
+    pub atmosphere: Option<Atmosphere>,
+    pub sun: Option<Sun>,
+}
+
+impl Scene {
+    pub fn into_environment(self) -> Environment {
+        Environment {
+            camera: self.camera.build(),
+            lights: self.world.lights(),
+            world: self.world.into_bvh(),
+            background: self.background,
+            atmosphere: self.atmosphere,
+            sun: self.sun,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    // a problem on the given (1-based) line of the scene file
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io(err) => write!(f, "could not read scene file: {}", err),
+            SceneError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+pub fn load_scene<P: AsRef<Path>>(path: P) -> Result<Scene, SceneError> {
+    let path = path.as_ref();
+    let src = std::fs::read_to_string(path).map_err(SceneError::Io)?;
+    return parse_scene(&src, path.parent().unwrap_or(Path::new("")));
+}
+
+// parse a scene description. files referred to by the scene are looked up relative to `base_dir`.
+pub fn parse_scene(src: &str, base_dir: &Path) -> Result<Scene, SceneError> {
+    let mut parser = Parser {
+        base_dir: base_dir.to_path_buf(),
+        camera: CameraSettings::default(),
+        world: HittableList::new(),
+        background: Background::default(),
+        atmosphere: None,
+        sun: None,
+        materials: HashMap::new(),
+        textures: HashMap::new(),
+        objects: HashMap::new(),
+        instanced: HashMap::new(),
+        seen_camera: false,
+        seen_background: false,
+    };
+    for (i, line) in src.lines().enumerate() {
+        let line_no = i + 1;
+        let content = match line.find('#') {
+            Some(idx) => &line[..idx],
+            None => line,
+        };
+        let mut tokens = content.split_whitespace();
+        let directive = match tokens.next() {
+            Some(directive) => directive,
+            None => continue,
+        };
+        parser
+            .directive(directive, tokens.collect())
+            .map_err(|message| SceneError::Parse {
+                line: line_no,
+                message,
+            })?;
+    }
+    return Ok(Scene {
+        camera: parser.camera,
+        world: parser.world,
+        background: parser.background,
+        atmosphere: parser.atmosphere,
+        sun: parser.sun,
+    });
+}
+
+struct Parser {
+    base_dir: PathBuf,
+    camera: CameraSettings,
+    world: HittableList,
+    background: Background,
+    atmosphere: Option<Atmosphere>,
+    sun: Option<Sun>,
+    materials: HashMap<String, ArcMaterial>,
+    textures: HashMap<String, ArcTexture>,
+    // objects which are only placed in the world through instances, by name. each is moved
+    // into `instanced` when it is first used.
+    objects: HashMap<String, HittableList>,
+    instanced: HashMap<String, Arc<dyn Hittable>>,
+    seen_camera: bool,
+    seen_background: bool,
+}
+
+impl Parser {
+    fn directive(&mut self, directive: &str, args: Vec<&str>) -> Result<(), String> {
+        match directive {
+            "camera" => self.camera(Fields::parse(directive, &args)?),
+            "background" => {
+                let kind = match args.first() {
+                    Some(kind) if !kind.contains('=') => *kind,
+                    _ => return Err("expected `background <kind> [fields...]`".to_string()),
+                };
+                if self.seen_background {
+                    return Err("the background is already defined".to_string());
+                }
+                self.seen_background = true;
+                let mut fields = Fields::parse(kind, &args[1..])?;
+                self.background = self.parse_background(kind, &mut fields)?;
+                fields.finish()
+            }
+            "atmosphere" => {
+                if self.atmosphere.is_some() {
+                    return Err("the atmosphere is already defined".to_string());
+                }
+                let mut fields = Fields::parse(directive, &args)?;
+                let atmosphere = Atmosphere {
+                    density: fields.f64("density")?,
+                    phase_function: Arc::new(Isotropic {
+                        albedo: solid(
+                            fields
+                                .opt_vec3("albedo")?
+                                .unwrap_or(Color::new(1.0, 1.0, 1.0)),
+                        ),
+                    }),
+                    center: fields.opt_vec3("center")?.unwrap_or(Point3::zeroes()),
+                    radius: fields.opt_f64("radius")?.unwrap_or(1000.0),
+                };
+                fields.finish()?;
+                if atmosphere.density <= 0.0 || atmosphere.radius <= 0.0 {
+                    return Err("atmosphere: `density` and `radius` must be positive".to_string());
+                }
+                self.atmosphere = Some(atmosphere);
+                Ok(())
+            }
+            "sun" => {
+                if self.sun.is_some() {
+                    return Err("the sun is already defined".to_string());
+                }
+                let mut fields = Fields::parse(directive, &args)?;
+                let direction = fields.vec3("direction")?;
+                let angle = fields.opt_f64("angle")?.unwrap_or(0.53);
+                let color = fields.vec3("color")?;
+                fields.finish()?;
+                if direction.near_zero() || angle <= 0.0 || angle >= 180.0 {
+                    return Err(
+                        "sun: `direction` must be non-zero, and `angle` in (0, 180)".to_string()
+                    );
+                }
+                self.sun = Some(Sun::new(direction, angle, color));
+                Ok(())
+            }
+            "texture" => {
+                let (name, kind) = match args.as_slice() {
+                    [name, kind, ..] if !name.contains('=') && !kind.contains('=') => {
+                        (*name, *kind)
+                    }
+                    _ => return Err("expected `texture <name> <kind> [fields...]`".to_string()),
+                };
+                if self.textures.contains_key(name) {
+                    return Err(format!("texture `{}` is already defined", name));
+                }
+                let mut fields = Fields::parse(kind, &args[2..])?;
+                let texture = self.parse_texture(kind, &mut fields)?;
+                fields.finish()?;
+                self.textures.insert(name.to_string(), texture);
+                Ok(())
+            }
+            "material" => {
+                let (name, kind) = match args.as_slice() {
+                    [name, kind, ..] if !name.contains('=') && !kind.contains('=') => {
+                        (*name, *kind)
+                    }
+                    _ => return Err("expected `material <name> <kind> [fields...]`".to_string()),
+                };
+                if self.materials.contains_key(name) {
+                    return Err(format!("material `{}` is already defined", name));
+                }
+                let mut fields = Fields::parse(kind, &args[2..])?;
+                let material = self.parse_material(kind, &mut fields)?;
+                fields.finish()?;
+                self.materials.insert(name.to_string(), material);
+                Ok(())
+            }
+            "object" => {
+                let (name, kind) = match args.as_slice() {
+                    [name, kind, ..] if !name.contains('=') && !kind.contains('=') => {
+                        (*name, *kind)
+                    }
+                    _ => return Err("expected `object <name> <directive> [fields...]`".to_string()),
+                };
+                if self.instanced.contains_key(name) {
+                    return Err(format!(
+                        "object `{}` can't be added to after it has been instanced",
+                        name
+                    ));
+                }
+                let objects = self.object(kind, &args[2..])?;
+                let group = self
+                    .objects
+                    .entry(name.to_string())
+                    .or_insert_with(HittableList::new);
+                for object in objects {
+                    group.add(object);
+                }
+                Ok(())
+            }
+            _ => {
+                for object in self.object(directive, &args)? {
+                    self.world.add(object);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // parse a directive which creates objects (a mesh, for instance, creates one per triangle)
+    fn object(&mut self, directive: &str, args: &[&str]) -> Result<Vec<Box<dyn Hittable>>, String> {
+        match directive {
+            "sphere" => {
+                let mut fields = Fields::parse(directive, args)?;
+                let sphere = Sphere {
+                    center: fields.vec3("center")?,
+                    radius: fields.f64("radius")?,
+                    mat_ptr: self.material(&mut fields)?,
+                };
+                self.place(vec![Box::new(sphere)], fields)
+            }
+            "moving_sphere" => {
+                let mut fields = Fields::parse(directive, args)?;
+                let sphere = MovingSphere {
+                    center0: fields.vec3("center0")?,
+                    center1: fields.vec3("center1")?,
+                    time0: fields.opt_f64("time0")?.unwrap_or(0.0),
+                    time1: fields.opt_f64("time1")?.unwrap_or(1.0),
+                    radius: fields.f64("radius")?,
+                    mat_ptr: self.material(&mut fields)?,
+                };
+                if sphere.time1 <= sphere.time0 {
+                    return Err("moving_sphere: `time1` must be after `time0`".to_string());
+                }
+                self.place(vec![Box::new(sphere)], fields)
+            }
+            "triangle" => {
+                let mut fields = Fields::parse(directive, args)?;
+                let triangle = Triangle {
+                    v0: fields.vec3("v0")?,
+                    v1: fields.vec3("v1")?,
+                    v2: fields.vec3("v2")?,
+                    mat_ptr: self.material(&mut fields)?,
+                };
+                self.place(vec![Box::new(triangle)], fields)
+            }
+            "plane" => {
+                let mut fields = Fields::parse(directive, args)?;
+                let normal = fields.vec3("normal")?;
+                if normal.near_zero() {
+                    return Err("plane: `normal` must not be zero".to_string());
+                }
+                let plane = Plane {
+                    point: fields.vec3("point")?,
+                    normal: normal.unit_vector(),
+                    mat_ptr: self.material(&mut fields)?,
+                };
+                self.place(vec![Box::new(plane)], fields)
+            }
+            "xy_rect" | "xz_rect" | "yz_rect" => {
+                let mut fields = Fields::parse(directive, args)?;
+                let rect = match directive {
+                    "xy_rect" => AaRect::xy(
+                        fields.f64("x0")?,
+                        fields.f64("x1")?,
+                        fields.f64("y0")?,
+                        fields.f64("y1")?,
+                        fields.f64("z")?,
+                        self.material(&mut fields)?,
+                    ),
+                    "xz_rect" => AaRect::xz(
+                        fields.f64("x0")?,
+                        fields.f64("x1")?,
+                        fields.f64("z0")?,
+                        fields.f64("z1")?,
+                        fields.f64("y")?,
+                        self.material(&mut fields)?,
+                    ),
+                    _ => AaRect::yz(
+                        fields.f64("y0")?,
+                        fields.f64("y1")?,
+                        fields.f64("z0")?,
+                        fields.f64("z1")?,
+                        fields.f64("x")?,
+                        self.material(&mut fields)?,
+                    ),
+                };
+                self.place(vec![Box::new(rect)], fields)
+            }
+            "box" => {
+                let mut fields = Fields::parse(directive, args)?;
+                let aabox = AaBox::new(
+                    fields.vec3("min")?,
+                    fields.vec3("max")?,
+                    self.material(&mut fields)?,
+                );
+                self.place(vec![Box::new(aabox)], fields)
+            }
+            "disk" => {
+                let mut fields = Fields::parse(directive, args)?;
+                let normal = fields.vec3("normal")?;
+                if normal.near_zero() {
+                    return Err("disk: `normal` must not be zero".to_string());
+                }
+                let disk = Disk {
+                    center: fields.vec3("center")?,
+                    normal: normal.unit_vector(),
+                    radius: fields.f64("radius")?,
+                    mat_ptr: self.material(&mut fields)?,
+                };
+                self.place(vec![Box::new(disk)], fields)
+            }
+            "cylinder" | "cone" => {
+                let mut fields = Fields::parse(directive, args)?;
+                let base = fields.vec3("base")?;
+                let top = fields.vec3("top")?;
+                if (top - base).near_zero() {
+                    return Err(format!("{}: `base` and `top` must differ", directive));
+                }
+                let radius = fields.f64("radius")?;
+                let capped = fields.opt_bool("capped")?.unwrap_or(true);
+                let mat_ptr = self.material(&mut fields)?;
+                let object: Box<dyn Hittable> = match directive {
+                    "cylinder" => Box::new(Cylinder {
+                        base,
+                        top,
+                        radius,
+                        capped,
+                        mat_ptr,
+                    }),
+                    _ => Box::new(Cone {
+                        base,
+                        apex: top,
+                        radius,
+                        capped,
+                        mat_ptr,
+                    }),
+                };
+                self.place(vec![object], fields)
+            }
+            "mesh" => {
+                let mut fields = Fields::parse(directive, args)?;
+                let mesh = self.load_mesh(&mut fields)?;
+                self.place(mesh.into_triangles(), fields)
+            }
+            "medium" => {
+                let mut fields = Fields::parse(directive, args)?;
+                let medium = ConstantMedium {
+                    boundary: self.instanced_object(&mut fields)?,
+                    density: fields.f64("density")?,
+                    phase_function: Arc::new(Isotropic {
+                        albedo: self.texture(&mut fields, "albedo")?,
+                    }),
+                };
+                if medium.density <= 0.0 {
+                    return Err("medium: `density` must be positive".to_string());
+                }
+                self.place(vec![Box::new(medium)], fields)
+            }
+            "instance" => {
+                let mut fields = Fields::parse(directive, args)?;
+                let object = self.instanced_object(&mut fields)?;
+                let transform = parse_transform(&mut fields)?.unwrap_or_else(Mat4::identity);
+                fields.finish()?;
+                Ok(vec![Box::new(Instance::new(object, transform))])
+            }
+            _ => Err(format!("unknown directive `{}`", directive)),
+        }
+    }
+
+    // apply the fields common to every object: any object can be turned inside out with
+    // `flip=true`, and moved with a transform. transformed objects are grouped into a single
+    // instance.
+    fn place(
+        &self,
+        mut objects: Vec<Box<dyn Hittable>>,
+        mut fields: Fields,
+    ) -> Result<Vec<Box<dyn Hittable>>, String> {
+        let flip = fields.opt_bool("flip")?.unwrap_or(false);
+        let transform = parse_transform(&mut fields)?;
+        fields.finish()?;
+
+        if flip {
+            objects = objects
+                .into_iter()
+                .map(|object| Box::new(FlipFace { object }) as Box<dyn Hittable>)
+                .collect();
+        }
+        let transform = match transform {
+            Some(transform) => transform,
+            None => return Ok(objects),
+        };
+        let shared: Arc<dyn Hittable> = match objects.len() {
+            1 => Arc::from(objects.pop().unwrap()),
+            _ => {
+                let mut group = HittableList::new();
+                for object in objects {
+                    group.add(object);
+                }
+                Arc::new(group.into_bvh())
+            }
+        };
+        return Ok(vec![Box::new(Instance::new(shared, transform))]);
+    }
+
+    // the object named by the `object` field. the first instance of an object builds it, after
+    // which it is shared by every other instance.
+    fn instanced_object(&mut self, fields: &mut Fields) -> Result<Arc<dyn Hittable>, String> {
+        let name = fields.str("object")?;
+        if let Some(object) = self.instanced.get(name) {
+            return Ok(Arc::clone(object));
+        }
+        let group = match self.objects.remove(name) {
+            Some(group) => group,
+            None => return Err(format!("instance: unknown object `{}`", name)),
+        };
+        let object: Arc<dyn Hittable> = Arc::new(group.into_bvh());
+        self.instanced.insert(name.to_string(), Arc::clone(&object));
+        return Ok(object);
+    }
+
+    fn camera(&mut self, mut fields: Fields) -> Result<(), String> {
+        if self.seen_camera {
+            return Err("the camera is already defined".to_string());
+        }
+        self.seen_camera = true;
+        let c = &mut self.camera;
+        c.look_from = fields.opt_vec3("look_from")?.unwrap_or(c.look_from);
+        c.look_at = fields.opt_vec3("look_at")?.unwrap_or(c.look_at);
+        c.vup = fields.opt_vec3("vup")?.unwrap_or(c.vup);
+        c.vfov = fields.opt_f64("vfov")?.unwrap_or(c.vfov);
+        c.aspect_ratio = fields.opt_f64("aspect_ratio")?.unwrap_or(c.aspect_ratio);
+        c.aperture = fields.opt_f64("aperture")?.unwrap_or(c.aperture);
+        c.focus_dist = fields.opt_f64("focus_dist")?.unwrap_or(c.focus_dist);
+        c.shutter_open = fields.opt_f64("shutter_open")?.unwrap_or(c.shutter_open);
+        c.shutter_close = fields.opt_f64("shutter_close")?.unwrap_or(c.shutter_close);
+        fields.finish()?;
+        return c.validate();
+    }
+
+    fn parse_background(&self, kind: &str, fields: &mut Fields) -> Result<Background, String> {
+        let background = match kind {
+            "solid" => Background::Solid(fields.vec3("color")?),
+            "gradient" => Background::Gradient {
+                bottom: fields.vec3("bottom")?,
+                top: fields.vec3("top")?,
+            },
+            "envmap" => {
+                let path = self.base_dir.join(fields.str("path")?);
+                let image = read_image(&path, Encoding::Srgb)
+                    .map_err(|err| format!("could not read `{}`: {}", path.display(), err))?;
+                Background::EnvironmentMap(EnvironmentMap::new(
+                    image,
+                    fields.opt_f64("intensity")?.unwrap_or(1.0),
+                    fields.opt_f64("rotation")?.unwrap_or(0.0),
+                ))
+            }
+            _ => return Err(format!("unknown background kind `{}`", kind)),
+        };
+        return Ok(background);
+    }
+
+    fn load_mesh(&self, fields: &mut Fields) -> Result<Mesh, String> {
+        let path = self.base_dir.join(fields.str("path")?);
+        let default_material = match fields.opt_str("material") {
+            Some(name) => Some(self.material_named(fields.directive, name)?),
+            None => None,
+        };
+        return load_obj(&path, default_material)
+            .map_err(|err| format!("could not load `{}`: {}", path.display(), err));
+    }
+
+    // look up the material named by the `material` field
+    fn material(&self, fields: &mut Fields) -> Result<ArcMaterial, String> {
+        let name = fields.str("material")?;
+        return self.material_named(fields.directive, name);
+    }
+
+    fn parse_material(&self, kind: &str, fields: &mut Fields) -> Result<ArcMaterial, String> {
+        let material: ArcMaterial = match kind {
+            "lambertian" => Arc::new(Lambertian {
+                albedo: self.texture(fields, "albedo")?,
+            }),
+            "metal" => Arc::new(Metal {
+                albedo: self.texture(fields, "albedo")?,
+                fuzz: fields.opt_f64("fuzz")?.unwrap_or(0.0),
+            }),
+            "dielectric" => {
+                let dispersion = parse_dispersion(fields)?;
+                let ir = match (&dispersion, fields.opt_f64("ir")?) {
+                    (None, Some(ir)) => ir,
+                    (None, None) => return Err("dielectric: missing field `ir`".to_string()),
+                    (Some(dispersion), None) => dispersion.ir(Dispersion::REFERENCE_WAVELENGTH),
+                    (Some(_), Some(_)) => {
+                        return Err(
+                            "dielectric: give either `ir` or a dispersion, not both".to_string()
+                        )
+                    }
+                };
+                let exterior_ir = fields.opt_f64("exterior_ir")?.unwrap_or(1.0);
+                if ir <= 0.0 || exterior_ir <= 0.0 {
+                    return Err("dielectric: `ir` and `exterior_ir` must be positive".to_string());
+                }
+                let absorption = fields.opt_vec3("absorption")?.unwrap_or(Color::zeroes());
+                if absorption.x < 0.0 || absorption.y < 0.0 || absorption.z < 0.0 {
+                    return Err("dielectric: `absorption` must not be negative".to_string());
+                }
+                Arc::new(Dielectric {
+                    ir,
+                    dispersion,
+                    exterior_ir,
+                    roughness: fields.opt_f64("roughness")?.unwrap_or(0.0).clamp(0.0, 1.0),
+                    absorption,
+                })
+            }
+            "thin_dielectric" => {
+                let ir = fields.f64("ir")?;
+                if ir <= 0.0 {
+                    return Err("thin_dielectric: `ir` must be positive".to_string());
+                }
+                let thickness = fields.opt_f64("thickness")?;
+                if thickness.is_some_and(|thickness| thickness < 0.0) {
+                    return Err("thin_dielectric: `thickness` must not be negative".to_string());
+                }
+                Arc::new(ThinDielectric { ir, thickness })
+            }
+            "principled" => Arc::new(Principled {
+                base_color: self.texture(fields, "base_color")?,
+                metallic: fields.opt_f64("metallic")?.unwrap_or(0.0),
+                roughness: fields.opt_f64("roughness")?.unwrap_or(0.5),
+                metallic_roughness: self.opt_texture(fields, "metallic_roughness")?,
+                ior: fields.opt_f64("ior")?.unwrap_or(1.5),
+            }),
+            "conductor" => {
+                let (eta, k) = match fields.opt_str("metal") {
+                    Some(name) => match metal_ior(name) {
+                        Some(ior) => ior,
+                        None => return Err(format!("conductor: unknown metal `{}`", name)),
+                    },
+                    None => (fields.vec3("eta")?, fields.vec3("k")?),
+                };
+                Arc::new(Conductor {
+                    eta,
+                    k,
+                    roughness: fields.opt_f64("roughness")?.unwrap_or(0.0).clamp(0.0, 1.0),
+                })
+            }
+            "coated" => {
+                let base = fields.str("base")?;
+                let ior = fields.opt_f64("ior")?.unwrap_or(1.5);
+                if ior < 1.0 {
+                    return Err("coated: `ior` must be at least 1".to_string());
+                }
+                Arc::new(Coated {
+                    base: self.material_named(fields.directive, base)?,
+                    ior,
+                    roughness: fields.opt_f64("roughness")?.unwrap_or(0.0).clamp(0.0, 1.0),
+                    tint: self
+                        .opt_texture(fields, "tint")?
+                        .unwrap_or_else(|| solid(Color::new(1.0, 1.0, 1.0))),
+                })
+            }
+            "mix" => {
+                let materials = fields
+                    .str("materials")?
+                    .split(',')
+                    .map(|name| self.material_named(fields.directive, name))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if materials.len() < 2 || materials.len() > MixMaterial::MAX_MATERIALS {
+                    return Err(format!(
+                        "mix: `materials` should name between two and {} materials",
+                        MixMaterial::MAX_MATERIALS
+                    ));
+                }
+                let weights = match fields.opt_str("weights") {
+                    Some(weights) => weights
+                        .split(',')
+                        .map(|weight| self.weight(fields, weight))
+                        .collect::<Result<Vec<_>, _>>()?,
+                    None => {
+                        let share = 1.0 / materials.len() as f64;
+                        vec![solid(Color::new(share, share, share)); materials.len() - 1]
+                    }
+                };
+                if weights.len() != materials.len() - 1 {
+                    return Err(format!(
+                        "mix: `weights` should have one value for each material after the first ({}), \
+                         found {}",
+                        materials.len() - 1,
+                        weights.len()
+                    ));
+                }
+                Arc::new(MixMaterial { materials, weights })
+            }
+            "diffuse_light" => Arc::new(DiffuseLight {
+                emit: self.texture(fields, "emit")?,
+            }),
+            _ => return Err(format!("unknown material kind `{}`", kind)),
+        };
+        return Ok(material);
+    }
+
+    fn parse_texture(&self, kind: &str, fields: &mut Fields) -> Result<ArcTexture, String> {
+        let texture: ArcTexture = match kind {
+            "solid" => solid(fields.vec3("color")?),
+            "checker" => {
+                let scale = fields.opt_f64("scale")?.unwrap_or(1.0);
+                if scale <= 0.0 {
+                    return Err("checker: `scale` must be positive".to_string());
+                }
+                Arc::new(Checker {
+                    even: self.texture(fields, "even")?,
+                    odd: self.texture(fields, "odd")?,
+                    scale,
+                })
+            }
+            "image" => {
+                let path = self.base_dir.join(fields.str("path")?);
+                let encoding = match fields.opt_bool("linear")? {
+                    Some(true) => Encoding::Linear,
+                    _ => Encoding::Srgb,
+                };
+                let image = read_image(&path, encoding)
+                    .map_err(|err| format!("could not read `{}`: {}", path.display(), err))?;
+                let wrap = match fields.opt_str("wrap") {
+                    None | Some("repeat") => WrapMode::Repeat,
+                    Some("clamp") => WrapMode::Clamp,
+                    Some("mirror") => WrapMode::Mirror,
+                    Some(wrap) => {
+                        return Err(format!(
+                            "image: `wrap` should be `repeat`, `clamp` or `mirror`, found `{}`",
+                            wrap
+                        ))
+                    }
+                };
+                Arc::new(ImageTexture { image, wrap })
+            }
+            "noise" => {
+                let depth = fields.opt_integer("depth")?.unwrap_or(7);
+                let style = match fields.opt_str("style") {
+                    None | Some("smooth") => NoiseStyle::Smooth,
+                    Some("turbulence") => NoiseStyle::Turbulence(depth),
+                    Some("marble") => NoiseStyle::Marble(depth),
+                    Some(style) => {
+                        return Err(format!(
+                            "noise: `style` should be `smooth`, `turbulence` or `marble`, \
+                             found `{}`",
+                            style
+                        ))
+                    }
+                };
+                let seed = fields.opt_integer("seed")?.unwrap_or(0);
+                let scale = fields.opt_f64("scale")?.unwrap_or(1.0);
+                if scale <= 0.0 {
+                    return Err("noise: `scale` must be positive".to_string());
+                }
+                Arc::new(NoiseTexture {
+                    noise: Perlin::new(&mut ChaCha8Rng::seed_from_u64(seed)),
+                    scale,
+                    style,
+                    color: fields
+                        .opt_vec3("color")?
+                        .unwrap_or(Color::new(1.0, 1.0, 1.0)),
+                })
+            }
+            _ => return Err(format!("unknown texture kind `{}`", kind)),
+        };
+        return Ok(texture);
+    }
+
+    // a field which is either a color `r,g,b`, or the name of a texture
+    fn texture(&self, fields: &mut Fields, key: &str) -> Result<ArcTexture, String> {
+        match self.opt_texture(fields, key)? {
+            Some(texture) => Ok(texture),
+            None => Err(format!("{}: missing field `{}`", fields.directive, key)),
+        }
+    }
+
+    fn opt_texture(&self, fields: &mut Fields, key: &str) -> Result<Option<ArcTexture>, String> {
+        let value = match fields.opt_str(key) {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        if value.contains(',') {
+            return Ok(Some(solid(fields.vector(key, value)?)));
+        }
+        match self.textures.get(value) {
+            Some(texture) => Ok(Some(Arc::clone(texture))),
+            None => Err(format!("{}: unknown texture `{}`", fields.directive, value)),
+        }
+    }
+
+    // one of a mix's weights: a number, or the name of a texture
+    fn weight(&self, fields: &Fields, value: &str) -> Result<ArcTexture, String> {
+        if let Some(texture) = self.textures.get(value) {
+            return Ok(Arc::clone(texture));
+        }
+        match value.parse::<f64>() {
+            Ok(weight) if weight.is_finite() => Ok(solid(Color::new(weight, weight, weight))),
+            _ => Err(format!(
+                "{}: each of `weights` should be a number or a texture, found `{}`",
+                fields.directive, value
+            )),
+        }
+    }
+
+    fn material_named(&self, directive: &str, name: &str) -> Result<ArcMaterial, String> {
+        match self.materials.get(name) {
+            Some(material) => Ok(Arc::clone(material)),
+            None => Err(format!("{}: unknown material `{}`", directive, name)),
+        }
+    }
+}
+
+// a dielectric's dispersion: a named material, `cauchy_a=... cauchy_b=...` or
+// `sellmeier_b=... sellmeier_c=...`, if any of them is given
+fn parse_dispersion(fields: &mut Fields) -> Result<Option<Dispersion>, String> {
+    if let Some(name) = fields.opt_str("glass") {
+        return match Dispersion::named(name) {
+            Some(dispersion) => Ok(Some(dispersion)),
+            None => Err(format!("{}: unknown glass `{}`", fields.directive, name)),
+        };
+    }
+    let dispersion = if let Some(a) = fields.opt_f64("cauchy_a")? {
+        let b = fields.f64("cauchy_b")?;
+        Dispersion::Cauchy { a, b }
+    } else if let Some(b) = fields.opt_vec3("sellmeier_b")? {
+        let c = fields.vec3("sellmeier_c")?;
+        Dispersion::Sellmeier {
+            b: [b.x, b.y, b.z],
+            c: [c.x, c.y, c.z],
+        }
+    } else {
+        return Ok(None);
+    };
+    if !dispersion.is_physical() {
+        return Err(format!(
+            "{}: the dispersion must give a finite index of refraction of at least 1 between \
+             {} and {} nm",
+            fields.directive, MIN_WAVELENGTH, MAX_WAVELENGTH
+        ));
+    }
+    return Ok(Some(dispersion));
+}
+
+// the optional `scale`, `rotate_x`, `rotate_y`, `rotate_z` and `translate` fields, applied in
+// that order. `scale` is either one factor or one per axis, and rotations are in degrees.
+fn parse_transform(fields: &mut Fields) -> Result<Option<Mat4>, String> {
+    let mut transform: Option<Mat4> = None;
+    let mut then = |step: Mat4| {
+        transform = Some(match transform {
+            Some(transform) => step * transform,
+            None => step,
+        });
+    };
+
+    match fields.opt_str("scale") {
+        Some(value) if value.contains(',') => {
+            then(Mat4::scaling(fields.vector("scale", value)?));
+        }
+        Some(value) => {
+            let s = fields.number("scale", value)?;
+            then(Mat4::scaling(Vec3::new(s, s, s)));
+        }
+        None => {}
+    }
+    let axes = [
+        ("rotate_x", Vec3::new(1.0, 0.0, 0.0)),
+        ("rotate_y", Vec3::new(0.0, 1.0, 0.0)),
+        ("rotate_z", Vec3::new(0.0, 0.0, 1.0)),
+    ];
+    for (key, axis) in axes {
+        if let Some(angle) = fields.opt_f64(key)? {
+            then(Mat4::rotation(axis, angle));
+        }
+    }
+    if let Some(offset) = fields.opt_vec3("translate")? {
+        then(Mat4::translation(offset));
+    }
+
+    if let Some(transform) = transform {
+        if transform.inverse().is_none() {
+            return Err(format!(
+                "{}: the transform is not invertible (is a scale factor zero?)",
+                fields.directive
+            ));
+        }
+    }
+    return Ok(transform);
+}
+
+// the `key=value` fields of a single directive. each field is removed as it is read, so that
+// any left over at the end can be reported as unknown.
+struct Fields<'a> {
+    directive: &'a str,
+    values: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Fields<'a> {
+    fn parse(directive: &'a str, args: &[&'a str]) -> Result<Self, String> {
+        let mut values: Vec<(&str, &str)> = Vec::new();
+        for arg in args {
+            let (key, value) = match arg.split_once('=') {
+                Some((key, value)) if !key.is_empty() && !value.is_empty() => (key, value),
+                _ => {
+                    return Err(format!(
+                        "{}: expected `key=value`, found `{}`",
+                        directive, arg
+                    ))
+                }
+            };
+            if values.iter().any(|(k, _)| *k == key) {
+                return Err(format!("{}: field `{}` is given twice", directive, key));
+            }
+            values.push((key, value));
+        }
+        return Ok(Self { directive, values });
+    }
+
+    fn opt_str(&mut self, key: &str) -> Option<&'a str> {
+        let idx = self.values.iter().position(|(k, _)| *k == key)?;
+        return Some(self.values.remove(idx).1);
+    }
+
+    fn str(&mut self, key: &str) -> Result<&'a str, String> {
+        match self.opt_str(key) {
+            Some(value) => Ok(value),
+            None => Err(format!("{}: missing field `{}`", self.directive, key)),
+        }
+    }
+
+    fn opt_f64(&mut self, key: &str) -> Result<Option<f64>, String> {
+        match self.opt_str(key) {
+            Some(value) => Ok(Some(self.number(key, value)?)),
+            None => Ok(None),
+        }
+    }
+
+    // a whole number, such as a count or a seed, which an unsigned `T` also keeps from being
+    // negative
+    fn opt_integer<T: std::str::FromStr>(&mut self, key: &str) -> Result<Option<T>, String> {
+        let value = match self.opt_str(key) {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        match value.parse::<T>() {
+            Ok(x) => Ok(Some(x)),
+            Err(_) => Err(format!(
+                "{}: field `{}` should be a whole number, found `{}`",
+                self.directive, key, value
+            )),
+        }
+    }
+
+    fn f64(&mut self, key: &str) -> Result<f64, String> {
+        let value = self.str(key)?;
+        return self.number(key, value);
+    }
+
+    fn opt_bool(&mut self, key: &str) -> Result<Option<bool>, String> {
+        match self.opt_str(key) {
+            Some("true") => Ok(Some(true)),
+            Some("false") => Ok(Some(false)),
+            Some(value) => Err(format!(
+                "{}: field `{}` should be `true` or `false`, found `{}`",
+                self.directive, key, value
+            )),
+            None => Ok(None),
+        }
+    }
+
+    fn opt_vec3(&mut self, key: &str) -> Result<Option<Vec3>, String> {
+        match self.opt_str(key) {
+            Some(value) => Ok(Some(self.vector(key, value)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn vec3(&mut self, key: &str) -> Result<Vec3, String> {
+        match self.opt_vec3(key)? {
+            Some(value) => Ok(value),
+            None => Err(format!("{}: missing field `{}`", self.directive, key)),
+        }
+    }
+
+    fn vector(&self, key: &str, value: &str) -> Result<Vec3, String> {
+        let parts: Vec<&str> = value.split(',').collect();
+        if parts.len() != 3 {
+            return Err(format!(
+                "{}: field `{}` should be a vector `x,y,z`, found `{}`",
+                self.directive, key, value
+            ));
+        }
+        return Ok(Vec3::new(
+            self.number(key, parts[0])?,
+            self.number(key, parts[1])?,
+            self.number(key, parts[2])?,
+        ));
+    }
+
+    fn number(&self, key: &str, value: &str) -> Result<f64, String> {
+        match value.parse::<f64>() {
+            Ok(x) if x.is_finite() => Ok(x),
+            _ => Err(format!(
+                "{}: field `{}` should be a number, found `{}`",
+                self.directive, key, value
+            )),
+        }
+    }
+
+    // check that every field has been used
+    fn finish(self) -> Result<(), String> {
+        match self.values.first() {
+            Some((key, _)) => Err(format!("{}: unknown field `{}`", self.directive, key)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hit_record::HitRecord;
+    use crate::ray::Ray;
+
+    // the message of the error from parsing `src`, which should fail
+    fn parse_error(src: &str) -> String {
+        match parse_scene(src, Path::new("")) {
+            Ok(_) => panic!("parsed a scene which should have been rejected:\n{}", src),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    fn parse(src: &str) -> Scene {
+        match parse_scene(src, Path::new("")) {
+            Ok(scene) => scene,
+            Err(err) => panic!("could not parse scene: {}\n{}", err, src),
+        }
+    }
+
+    fn assert_vec3(v: Vec3, expected: (f64, f64, f64)) {
+        assert!(
+            (v - Vec3::new(expected.0, expected.1, expected.2)).length() < 1e-9,
+            "expected {:?}, found {:?}",
+            expected,
+            v
+        );
+    }
+
+    fn assert_rejected(src: &str, expected: &str) {
+        let message = parse_error(src);
+        assert!(
+            message.contains(expected),
+            "expected an error mentioning `{}`, found `{}`",
+            expected,
+            message
+        );
+    }
+
+    // every example scene shipped alongside the renderer
+    #[test]
+    fn parses_example_scenes() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes");
+        let mut count = 0;
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "scene") {
+                let scene =
+                    load_scene(&path).unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+                assert!(!scene.world.is_empty(), "{} has no objects", path.display());
+                count += 1;
+            }
+        }
+        assert!(count > 0, "no scenes found in {}", dir.display());
+    }
+
+    #[test]
+    fn parses_camera() {
+        let camera = parse("camera look_from=1,2,3 look_at=0,1,0 vfov=40 aperture=0").camera;
+        assert_vec3(camera.look_from, (1.0, 2.0, 3.0));
+        assert_vec3(camera.look_at, (0.0, 1.0, 0.0));
+        assert_eq!(camera.vfov, 40.0);
+        assert_eq!(camera.aperture, 0.0);
+        // fields which are left out keep their defaults
+        let default = CameraSettings::default();
+        assert_vec3(camera.vup, (default.vup.x, default.vup.y, default.vup.z));
+        assert_eq!(camera.aspect_ratio, default.aspect_ratio);
+        assert_eq!(camera.focus_dist, default.focus_dist);
+    }
+
+    #[test]
+    fn parses_objects_and_materials() {
+        let scene = parse(
+            "material lamp diffuse_light emit=4,3,2\n\
+             material ground lambertian albedo=0.5,0.5,0.5\n\
+             sphere center=0,0,-2 radius=0.5 material=lamp  # a light\n\
+             box min=-1,-3,-1 max=1,-2,1 material=ground",
+        );
+        assert_eq!(scene.world.len(), 2);
+        assert_eq!(scene.world.lights().len(), 1);
+        let bbox = scene.world.bounding_box().unwrap();
+        assert_vec3(bbox.minimum, (-1.0, -3.0, -2.5));
+        assert_vec3(bbox.maximum, (1.0, 0.5, 1.0));
+
+        // a ray straight down -z hits the front of the sphere, which glows with `emit`
+        let ray = Ray {
+            origin: Point3::zeroes(),
+            dir: Vec3::new(0.0, 0.0, -1.0),
+            time: 0.0,
+            wavelength: None,
+        };
+        let mut record = HitRecord::dummy();
+        let mut gen = ChaCha8Rng::seed_from_u64(0);
+        assert!(scene
+            .world
+            .hit(&ray, 0.001, f64::INFINITY, &mut record, &mut gen));
+        assert!((record.t - 1.5).abs() < 1e-9, "hit at t = {}", record.t);
+        assert_vec3(record.normal, (0.0, 0.0, 1.0));
+        let material = record.mat_ptr.clone().unwrap();
+        assert_vec3(material.emitted(&ray, &record), (4.0, 3.0, 2.0));
+    }
+
+    #[test]
+    fn checker_rejects_non_positive_scale() {
+        assert_rejected(
+            "texture checks checker even=1,1,1 odd=0,0,0 scale=0",
+            "`scale`",
+        );
+    }
+
+    #[test]
+    fn noise_rejects_non_positive_scale() {
+        assert_rejected("texture grain noise scale=-2", "`scale`");
+    }
+
+    #[test]
+    fn noise_rejects_negative_or_fractional_depth() {
+        assert_rejected("texture grain noise style=marble depth=-3", "`depth`");
+        assert_rejected("texture grain noise style=marble depth=2.7", "`depth`");
+    }
+
+    #[test]
+    fn noise_rejects_negative_or_fractional_seed() {
+        assert_rejected("texture grain noise seed=-1", "`seed`");
+        assert_rejected("texture grain noise seed=1.5", "`seed`");
+    }
+
+    #[test]
+    fn dielectric_rejects_non_positive_ir() {
+        assert_rejected("material glass dielectric ir=0", "`ir`");
+        assert_rejected("material glass dielectric ir=-1.5", "`ir`");
+    }
+
+    #[test]
+    fn dielectric_rejects_non_positive_exterior_ir() {
+        assert_rejected(
+            "material glass dielectric ir=1.5 exterior_ir=0",
+            "`exterior_ir`",
+        );
+    }
+
+    #[test]
+    fn dielectric_rejects_negative_absorption() {
+        assert_rejected(
+            "material glass dielectric ir=1.5 absorption=0.1,-0.2,0",
+            "`absorption`",
+        );
+    }
+
+    #[test]
+    fn thin_dielectric_rejects_non_positive_ir() {
+        assert_rejected("material pane thin_dielectric ir=0", "`ir`");
+    }
+
+    #[test]
+    fn thin_dielectric_rejects_negative_thickness() {
+        assert_rejected(
+            "material bubble thin_dielectric ir=1.33 thickness=-300",
+            "`thickness`",
+        );
+    }
+
+    #[test]
+    fn dispersion_rejects_cauchy_below_one() {
+        assert_rejected(
+            "material glass dielectric cauchy_a=0.5 cauchy_b=0.004",
+            "dispersion",
+        );
+    }
+
+    #[test]
+    fn dispersion_rejects_sellmeier_pole_in_visible_range() {
+        // a resonance at 500 nm
+        assert_rejected(
+            "material glass dielectric sellmeier_b=1,0,0 sellmeier_c=0.25,0,0",
+            "dispersion",
+        );
+    }
+
+    #[test]
+    fn dispersion_rejects_negative_sellmeier_index() {
+        assert_rejected(
+            "material glass dielectric sellmeier_b=-2,0,0 sellmeier_c=0.01,0,0",
+            "dispersion",
+        );
+    }
+
+    #[test]
+    fn coated_rejects_ior_below_one() {
+        assert_rejected(
+            "material red lambertian albedo=0.8,0.1,0.1\n\
+             material paint coated base=red ior=0.9",
+            "`ior`",
+        );
+    }
+}
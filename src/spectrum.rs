@@ -0,0 +1,195 @@
+// spectral rendering. each path carries a few wavelengths of light rather than red, green and
+// blue: a "hero" wavelength chosen at random, and others spaced evenly across the visible
+// spectrum from it (Wilkie et al., "Hero Wavelength Spectral Sampling", 2014). the value of a
+// path at its wavelengths is packed into a `Color`, one wavelength per channel.
+//
+// materials, textures and lights still give RGB colors, which are turned into spectra with
+// Smits' method ("An RGB-to-Spectrum Conversion for Reflectances", 1999) and looked up at the
+// path's wavelengths. the light found by the path is weighted by the CIE color matching
+// functions into XYZ, and from there converted to linear sRGB.
+
+use crate::color::{chromatic_adaptation, cie_xyz, multiply, transform, Mat3, XYZ_TO_SRGB};
+use crate::vec3::Color;
+use std::sync::OnceLock;
+
+// the range of wavelengths, in nanometres, which paths carry
+pub const MIN_WAVELENGTH: f64 = 380.0;
+pub const MAX_WAVELENGTH: f64 = 780.0;
+
+// the number of wavelengths carried by each path (one per channel of a `Color`)
+const NUM_WAVELENGTHS: usize = 3;
+
+// Smits' basis spectra, as reflectances in 10 bins evenly spread over [380, 720] nm
+const SMITS_WHITE: [f64; 10] = [
+    1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000,
+];
+const SMITS_CYAN: [f64; 10] = [
+    0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000,
+];
+const SMITS_MAGENTA: [f64; 10] = [
+    1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959,
+];
+const SMITS_YELLOW: [f64; 10] = [
+    0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840,
+];
+const SMITS_RED: [f64; 10] = [
+    0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149,
+];
+const SMITS_GREEN: [f64; 10] = [
+    0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025,
+];
+const SMITS_BLUE: [f64; 10] = [
+    1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496,
+];
+const SMITS_MIN_WAVELENGTH: f64 = 380.0;
+const SMITS_MAX_WAVELENGTH: f64 = 720.0;
+
+// the values of Smits' basis spectra at a single wavelength
+#[derive(Copy, Clone)]
+struct SmitsBasis {
+    white: f64,
+    cyan: f64,
+    magenta: f64,
+    yellow: f64,
+    red: f64,
+    green: f64,
+    blue: f64,
+}
+
+impl SmitsBasis {
+    fn at(wavelength: f64) -> Self {
+        // linear interpolation between the centres of the bins, held constant beyond them
+        let bin_width = (SMITS_MAX_WAVELENGTH - SMITS_MIN_WAVELENGTH) / 10.0;
+        let x = ((wavelength - SMITS_MIN_WAVELENGTH) / bin_width - 0.5).clamp(0.0, 9.0);
+        let i = usize::min(x as usize, 8);
+        let f = x - i as f64;
+        let lerp = |bins: &[f64; 10]| (1.0 - f) * bins[i] + f * bins[i + 1];
+        Self {
+            white: lerp(&SMITS_WHITE),
+            cyan: lerp(&SMITS_CYAN),
+            magenta: lerp(&SMITS_MAGENTA),
+            yellow: lerp(&SMITS_YELLOW),
+            red: lerp(&SMITS_RED),
+            green: lerp(&SMITS_GREEN),
+            blue: lerp(&SMITS_BLUE),
+        }
+    }
+
+    // the value of the spectrum for the RGB color `c`, built from the white spectrum and then
+    // the secondary and primary spectra covering what's left of the two larger channels
+    fn value(&self, c: Color) -> f64 {
+        let (r, g, b) = (c.x, c.y, c.z);
+        if r <= g && r <= b {
+            let rest = match g <= b {
+                true => (g - r) * self.cyan + (b - g) * self.blue,
+                false => (b - r) * self.cyan + (g - b) * self.green,
+            };
+            return r * self.white + rest;
+        }
+        if g <= r && g <= b {
+            let rest = match r <= b {
+                true => (r - g) * self.magenta + (b - r) * self.blue,
+                false => (b - g) * self.magenta + (r - b) * self.red,
+            };
+            return g * self.white + rest;
+        }
+        let rest = match r <= g {
+            true => (r - b) * self.yellow + (g - r) * self.green,
+            false => (g - b) * self.yellow + (r - g) * self.red,
+        };
+        return b * self.white + rest;
+    }
+}
+
+// the constants needed to turn spectral samples into sRGB, worked out once
+struct Conversion {
+    // the integral of the CIE y matching function over the wavelengths carried by paths, so that
+    // a constant spectrum of 1 has a luminance of 1
+    y_integral: f64,
+    // from XYZ to linear sRGB, adapting the white of a constant spectrum to that of sRGB so that
+    // a white surface under a white light looks white
+    xyz_to_srgb: Mat3,
+}
+
+fn conversion() -> &'static Conversion {
+    static CONVERSION: OnceLock<Conversion> = OnceLock::new();
+    return CONVERSION.get_or_init(|| {
+        let steps = (MAX_WAVELENGTH - MIN_WAVELENGTH) as usize;
+        let y_integral = (0..steps)
+            .map(|i| cie_xyz(MIN_WAVELENGTH + i as f64 + 0.5).y)
+            .sum();
+        let equal_energy = (1.0 / 3.0, 1.0 / 3.0);
+        let d65 = (0.31271, 0.32902);
+        Conversion {
+            y_integral,
+            xyz_to_srgb: multiply(&chromatic_adaptation(equal_energy, d65), &XYZ_TO_SRGB),
+        }
+    });
+}
+
+// the wavelengths carried by a path
+pub struct SampledWavelengths {
+    // in nanometres, with the hero wavelength first
+    pub wavelengths: [f64; NUM_WAVELENGTHS],
+    basis: [SmitsBasis; NUM_WAVELENGTHS],
+    // whether the path has passed through something (such as a prism) which sent each
+    // wavelength a different way, after which only the hero wavelength is followed
+    secondary_terminated: bool,
+}
+
+impl SampledWavelengths {
+    // a hero wavelength chosen uniformly by `u` in [0, 1), with the others following it at
+    // equal spacing, wrapping around from the longest wavelength to the shortest
+    pub fn sample(u: f64) -> Self {
+        let range = MAX_WAVELENGTH - MIN_WAVELENGTH;
+        let wavelengths: [f64; NUM_WAVELENGTHS] = std::array::from_fn(|i| {
+            let offset = (u + i as f64 / NUM_WAVELENGTHS as f64).fract();
+            MIN_WAVELENGTH + offset * range
+        });
+        Self {
+            wavelengths,
+            basis: wavelengths.map(SmitsBasis::at),
+            secondary_terminated: false,
+        }
+    }
+
+    pub fn hero(&self) -> f64 {
+        self.wavelengths[0]
+    }
+
+    // the values at these wavelengths of the spectrum for the RGB color `c`, packed into a
+    // color. negative channels are treated as zero.
+    pub fn upsample(&self, c: Color) -> Color {
+        let c = Color::new(c.x.max(0.0), c.y.max(0.0), c.z.max(0.0));
+        return Color::new(
+            self.basis[0].value(c),
+            self.basis[1].value(c),
+            self.basis[2].value(c),
+        );
+    }
+
+    // follow only the hero wavelength from here on
+    pub fn terminate_secondary(&mut self) {
+        self.secondary_terminated = true;
+    }
+
+    // the linear sRGB color of the light found by a path, given its value at each wavelength
+    pub fn to_rgb(&self, values: Color) -> Color {
+        let values = [values.x, values.y, values.z];
+        let count = match self.secondary_terminated {
+            true => 1,
+            false => NUM_WAVELENGTHS,
+        };
+        // each wavelength is an estimate of the whole spectrum, with a uniform density
+        let range = MAX_WAVELENGTH - MIN_WAVELENGTH;
+        let mut xyz = Color::zeroes();
+        for (value, wavelength) in values.iter().zip(self.wavelengths).take(count) {
+            xyz += value * range * cie_xyz(wavelength);
+        }
+        let conversion = conversion();
+        return transform(
+            &conversion.xyz_to_srgb,
+            xyz / (count as f64 * conversion.y_integral),
+        );
+    }
+}
@@ -1,8 +1,13 @@
+use crate::aabb::Aabb;
 use crate::hit_record::HitRecord;
-use crate::hittable::Hittable;
+use crate::hittable::{area_pdf_to_solid_angle, Hittable};
 use crate::material::Material;
+use crate::onb::Onb;
+use crate::random::{random_in_cone, random_on_unit_sphere};
 use crate::ray::Ray;
 use crate::vec3::{Point3, Vec3};
+use rand::RngCore;
+use std::f64::consts::PI;
 use std::sync::Arc;
 
 pub struct Sphere {
@@ -12,33 +17,121 @@ pub struct Sphere {
 }
 
 impl Hittable for Sphere {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, record: &mut HitRecord) -> bool {
-        let oc: Vec3 = ray.origin - self.center;
-        let a = ray.dir.length_squared(); // equivalent to `dir.dot(dir)`
-        let half_b = ray.dir.dot(&oc);
-        let c = oc.length_squared() - (self.radius * self.radius);
-        let discriminant = (half_b * half_b) - (a * c);
-        if discriminant < 0.0 {
-            return false;
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        record: &mut HitRecord,
+        _gen: &mut dyn RngCore,
+    ) -> bool {
+        hit_sphere(
+            self.center,
+            self.radius,
+            &self.mat_ptr,
+            ray,
+            t_min,
+            t_max,
+            record,
+        )
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(sphere_bounding_box(self.center, self.radius))
+    }
+
+    fn is_light(&self) -> bool {
+        self.mat_ptr.is_emissive()
+    }
+
+    fn pdf_value(&self, ray: &Ray, _gen: &mut dyn RngCore) -> f64 {
+        let mut record = HitRecord::dummy();
+        let hit = hit_sphere(
+            self.center,
+            self.radius,
+            &self.mat_ptr,
+            ray,
+            0.001,
+            f64::INFINITY,
+            &mut record,
+        );
+        if !hit {
+            return 0.0;
+        }
+        let distance_squared = (self.center - ray.origin).length_squared();
+        if distance_squared <= self.radius * self.radius {
+            // from inside, points are chosen uniformly over the whole surface
+            let area = 4.0 * PI * self.radius * self.radius;
+            return area_pdf_to_solid_angle(ray, record.t, record.normal, area);
         }
+        let cos_max = f64::sqrt(1.0 - self.radius * self.radius / distance_squared);
+        return 1.0 / (2.0 * PI * (1.0 - cos_max));
+    }
 
-        // find closest intersecting point that satisfies t_min < t < t_max
-        let out_of_bounds = |t| t < t_min || t_max < t;
-        let sqrtd = discriminant.sqrt();
-        let mut root = (-half_b - sqrtd) / a;
-        if out_of_bounds(root) {
-            root = (-half_b + sqrtd) / a;
-            if out_of_bounds(root) {
-                return false;
-            }
+    fn random(&self, origin: Point3, gen: &mut dyn RngCore) -> Vec3 {
+        let direction = self.center - origin;
+        let distance_squared = direction.length_squared();
+        if distance_squared <= self.radius * self.radius {
+            let point = self.center + self.radius * random_on_unit_sphere(gen);
+            return point - origin;
         }
+        // from outside, sample the cone of directions which the sphere fills
+        let cos_max = f64::sqrt(1.0 - self.radius * self.radius / distance_squared);
+        return Onb::from_w(direction).to_world(random_in_cone(cos_max, gen));
+    }
+}
+
+// intersect a ray with the sphere of the given `center` and `radius`, which is shared with
+// `MovingSphere`
+pub fn hit_sphere(
+    center: Point3,
+    radius: f64,
+    mat_ptr: &Arc<dyn Material + Send + Sync>,
+    ray: &Ray,
+    t_min: f64,
+    t_max: f64,
+    record: &mut HitRecord,
+) -> bool {
+    let oc: Vec3 = ray.origin - center;
+    let a = ray.dir.length_squared(); // equivalent to `dir.dot(dir)`
+    let half_b = ray.dir.dot(&oc);
+    let c = oc.length_squared() - (radius * radius);
+    let discriminant = (half_b * half_b) - (a * c);
+    if discriminant < 0.0 {
+        return false;
+    }
 
-        // update the hit-record
-        record.t = root;
-        record.p = ray.at(root);
-        record.mat_ptr = Some(Arc::clone(&self.mat_ptr));
-        let outward_normal = (record.p - self.center) / self.radius;
-        record.set_face_normal(&ray, outward_normal);
-        return true;
+    // find closest intersecting point that satisfies t_min < t < t_max
+    let out_of_bounds = |t| t < t_min || t_max < t;
+    let sqrtd = discriminant.sqrt();
+    let mut root = (-half_b - sqrtd) / a;
+    if out_of_bounds(root) {
+        root = (-half_b + sqrtd) / a;
+        if out_of_bounds(root) {
+            return false;
+        }
     }
+
+    // update the hit-record
+    record.t = root;
+    record.p = ray.at(root);
+    record.mat_ptr = Some(Arc::clone(mat_ptr));
+    let outward_normal = (record.p - center) / radius;
+    (record.u, record.v) = sphere_uv(outward_normal);
+    record.set_face_normal(ray, outward_normal);
+    return true;
+}
+
+// the surface co-ordinates of a point on the unit sphere, given as its normal. `u` is the
+// angle around the y axis (starting from -x), and `v` the angle from the bottom (-y) to the
+// top, both scaled into [0, 1].
+fn sphere_uv(n: Vec3) -> (f64, f64) {
+    let theta = f64::acos((-n.y).clamp(-1.0, 1.0));
+    let phi = f64::atan2(-n.z, n.x) + PI;
+    return (phi / (2.0 * PI), theta / PI);
+}
+
+pub fn sphere_bounding_box(center: Point3, radius: f64) -> Aabb {
+    let r = Vec3::new(radius, radius, radius).abs();
+    return Aabb::new(center - r, center + r);
 }
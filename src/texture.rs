@@ -0,0 +1,43 @@
+use crate::vec3::{Color, Point3};
+use std::sync::Arc;
+
+// a color which varies over a surface, looked up by the surface co-ordinates (u, v) of a hit
+// and its position `p` in space
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f64, v: f64, p: Point3) -> Color;
+}
+
+pub struct SolidColor {
+    pub color: Color,
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _p: Point3) -> Color {
+        self.color
+    }
+}
+
+// a texture which is the same color everywhere, for materials given a plain color
+pub fn solid(color: Color) -> Arc<dyn Texture> {
+    return Arc::new(SolidColor { color });
+}
+
+// a 3D checkerboard of cubes `scale` wide, alternating between two textures. being solid, it
+// doesn't depend on how the surface is parameterised.
+pub struct Checker {
+    pub even: Arc<dyn Texture>,
+    pub odd: Arc<dyn Texture>,
+    pub scale: f64,
+}
+
+impl Texture for Checker {
+    fn value(&self, u: f64, v: f64, p: Point3) -> Color {
+        // nudged so that a surface lying exactly on a cell boundary (such as a ground plane at
+        // y = 0) doesn't flicker between cells with rounding errors in the hit point
+        let cell = |x: f64| (x / self.scale + 1e-6).floor() as i64;
+        match (cell(p.x) + cell(p.y) + cell(p.z)).rem_euclid(2) {
+            0 => self.even.value(u, v, p),
+            _ => self.odd.value(u, v, p),
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use crate::color::{cie_xyz, transform, XYZ_TO_SRGB};
+use crate::hit_record::HitRecord;
+use crate::material::{Material, ScatterRecord};
+use crate::microfacet::{fresnel_dielectric, reflect};
+use crate::ray::Ray;
+use crate::spectrum::{MAX_WAVELENGTH, MIN_WAVELENGTH};
+use crate::vec3::Color;
+use rand::{Rng, RngCore};
+use std::f64::consts::PI;
+
+// the number of wavelengths over which thin film interference is added up
+const NUM_WAVELENGTHS: usize = 40;
+
+// an infinitely thin transparent sheet, such as a window pane or a soap bubble, which is the same
+// from both sides. light either reflects off it or passes straight through, without being bent.
+//
+// without a `thickness`, the sheet is thick enough (compared with the wavelength of light) for
+// the many reflections between its two faces to add up incoherently, like a pane of glass. with
+// one, the sheet is a film so thin that those reflections interfere, giving the shifting colors
+// of a soap bubble.
+pub struct ThinDielectric {
+    // index of refraction
+    pub ir: f64,
+    // the thickness of the film in nanometres, for interference
+    pub thickness: Option<f64>,
+}
+
+impl ThinDielectric {
+    // the fraction of light reflected off the sheet, which arrives at an angle with cosine
+    // `cosine`. the rest passes through.
+    fn reflectance(&self, cosine: f64) -> Color {
+        let thickness = match self.thickness {
+            Some(thickness) => thickness,
+            None => {
+                // the sum of the light reflected after each trip back and forth within the sheet
+                let r = fresnel_dielectric(cosine, self.ir);
+                let t = 1.0 - r;
+                let total = r + t * t * r / (1.0 - r * r);
+                return Color::new(1.0, 1.0, 1.0) * total;
+            }
+        };
+
+        // the film's reflectance of white light, seen in sRGB
+        let mut xyz = Color::zeroes();
+        let mut white = Color::zeroes();
+        for i in 0..NUM_WAVELENGTHS {
+            let wavelength = MIN_WAVELENGTH
+                + (i as f64 + 0.5) * (MAX_WAVELENGTH - MIN_WAVELENGTH) / NUM_WAVELENGTHS as f64;
+            let cmf = cie_xyz(wavelength);
+            xyz += film_reflectance(cosine, self.ir, thickness, wavelength) * cmf;
+            white += cmf;
+        }
+        let rgb = transform(&XYZ_TO_SRGB, xyz) / transform(&XYZ_TO_SRGB, white);
+        return Color::new(
+            rgb.x.clamp(0.0, 1.0),
+            rgb.y.clamp(0.0, 1.0),
+            rgb.z.clamp(0.0, 1.0),
+        );
+    }
+}
+
+// the reflectance at one wavelength of a film of index of refraction `ir`, `thickness`
+// nanometres thick and surrounded by air, from the interference of all the waves reflected
+// within it (the Airy summation)
+fn film_reflectance(cosine: f64, ir: f64, thickness: f64, wavelength: f64) -> f64 {
+    let cos_i = cosine.clamp(0.0, 1.0);
+    let sin2_t = (1.0 - cos_i * cos_i) / (ir * ir);
+    if sin2_t >= 1.0 {
+        // total internal reflection, in a film less dense than the air around it
+        return 1.0;
+    }
+    let cos_t = f64::sqrt(1.0 - sin2_t);
+    // the phase difference between successive reflections
+    let delta = 4.0 * PI * ir * thickness * cos_t / wavelength;
+    // the amplitude reflected at the top face, for each polarisation. the bottom face reflects
+    // the same amplitude with the opposite sign.
+    let r_perpendicular = (cos_i - ir * cos_t) / (cos_i + ir * cos_t);
+    let r_parallel = (ir * cos_i - cos_t) / (ir * cos_i + cos_t);
+    let airy = |r: f64| {
+        let r2 = r * r;
+        let c = 2.0 * r2 * (1.0 - delta.cos());
+        c / (1.0 + r2 * r2 - 2.0 * r2 * delta.cos())
+    };
+    return 0.5 * (airy(r_perpendicular) + airy(r_parallel));
+}
+
+impl Material for ThinDielectric {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        record: &HitRecord,
+        gen: &mut dyn RngCore,
+    ) -> Option<ScatterRecord> {
+        let wo = -ray.dir.unit_vector();
+        let reflectance = self.reflectance(wo.dot(&record.normal));
+        let transmittance = Color::new(1.0, 1.0, 1.0) - reflectance;
+
+        // choose in proportion to the average of each
+        let p = (reflectance.x + reflectance.y + reflectance.z) / 3.0;
+        let (dir, attenuation) = match gen.gen::<f64>() < p {
+            true => (reflect(wo, record.normal), reflectance / p),
+            false => (ray.dir, transmittance / (1.0 - p)),
+        };
+        return Some(ScatterRecord {
+            ray: Ray {
+                origin: record.p,
+                dir,
+                time: ray.time,
+                wavelength: ray.wavelength,
+            },
+            attenuation,
+            pdf: None,
+            dispersive: false,
+        });
+    }
+}
@@ -0,0 +1,151 @@
+// turning the linear radiance of a render into colours a display can show. a colour is white
+// balanced, scaled by the exposure and then passed through a tone curve, which leaves it in
+// [0, 1] ready to be sRGB encoded.
+
+use crate::color::{black_body_chromaticity, chromatic_adaptation, luminance, transform, Mat3};
+use crate::image::Image;
+use crate::vec3::Color;
+
+// the colour temperature, in kelvin, which white balance leaves unchanged. this is the
+// temperature of the D65 white point.
+const NEUTRAL_TEMPERATURE: f64 = 6504.0;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ToneMapper {
+    // leave the colour as it is, so anything brighter than 1 is clipped
+    Clamp,
+    // Reinhard's operator, applied to the luminance: each colour is scaled by 1 / (1 + L), which
+    // keeps its hue but can still clip very bright saturated colours
+    Reinhard,
+    // Stephen Hill's fit of the ACES reference rendering and sRGB output transforms
+    Aces,
+    // Troy Sobotka's AgX, as fitted by Benjamin Wrensch. highlights desaturate towards white
+    // rather than skewing in hue.
+    Agx,
+}
+
+impl ToneMapper {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "clamp" => Some(ToneMapper::Clamp),
+            "reinhard" => Some(ToneMapper::Reinhard),
+            "aces" => Some(ToneMapper::Aces),
+            "agx" => Some(ToneMapper::Agx),
+            _ => None,
+        }
+    }
+
+    fn apply(&self, c: Color) -> Color {
+        match self {
+            ToneMapper::Clamp => c,
+            ToneMapper::Reinhard => c / (1.0 + luminance(c)),
+            ToneMapper::Aces => aces(c),
+            ToneMapper::Agx => agx(c),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ToneMapping {
+    // in stops: each one doubles the brightness
+    pub exposure: f64,
+    pub tone_mapper: ToneMapper,
+    // the colour temperature, in kelvin, of light which should appear white, if any
+    pub white_balance: Option<f64>,
+}
+
+impl Default for ToneMapping {
+    // no change, beyond clipping to [0, 1]
+    fn default() -> Self {
+        Self {
+            exposure: 0.0,
+            tone_mapper: ToneMapper::Clamp,
+            white_balance: None,
+        }
+    }
+}
+
+impl ToneMapping {
+    pub fn apply(&self, image: &Image) -> Image {
+        let white_balance = self.white_balance.map(|temperature| {
+            chromatic_adaptation(
+                black_body_chromaticity(temperature),
+                black_body_chromaticity(NEUTRAL_TEMPERATURE),
+            )
+        });
+        let scale = f64::powf(2.0, self.exposure);
+
+        let mut output = Image::zeroes(image.width, image.height);
+        for (out_row, row) in output.pixels.iter_mut().zip(&image.pixels) {
+            for (out, &c) in out_row.iter_mut().zip(row) {
+                let c = match &white_balance {
+                    Some(m) => transform(m, c),
+                    None => c,
+                };
+                // filters with negative lobes can leave slightly negative values
+                let c = scale * non_negative(c);
+                *out = self.tone_mapper.apply(c);
+            }
+        }
+        return output;
+    }
+}
+
+fn non_negative(c: Color) -> Color {
+    Color::new(c.x.max(0.0), c.y.max(0.0), c.z.max(0.0))
+}
+
+fn aces(c: Color) -> Color {
+    // from linear sRGB to the ACES rendering space, with the reference rendering transform's
+    // saturation adjustment folded in
+    const INPUT: Mat3 = [
+        [0.59719, 0.35458, 0.04823],
+        [0.07600, 0.90834, 0.01566],
+        [0.02840, 0.13383, 0.83777],
+    ];
+    // back to linear sRGB, with the output transform's desaturation folded in
+    const OUTPUT: Mat3 = [
+        [1.60475, -0.53108, -0.07367],
+        [-0.10208, 1.10813, -0.00605],
+        [-0.00327, -0.07276, 1.07602],
+    ];
+    let curve =
+        |v: f64| (v * (v + 0.0245786) - 0.000090537) / (v * (0.983729 * v + 0.4329510) + 0.238081);
+
+    let c = transform(&INPUT, c);
+    let c = Color::new(curve(c.x), curve(c.y), curve(c.z));
+    return transform(&OUTPUT, c);
+}
+
+fn agx(c: Color) -> Color {
+    // into AgX's working space, which pulls the primaries in a little so that bright
+    // saturated colours move towards white
+    const INSET: Mat3 = [
+        [0.842479062253094, 0.0784335999999992, 0.0792237451477643],
+        [0.0423282422610123, 0.878468636469772, 0.0791661274605434],
+        [0.0423756549057051, 0.0784336, 0.879142973793104],
+    ];
+    const OUTSET: Mat3 = [
+        [1.19687900512017, -0.0980208811401368, -0.0990297440797205],
+        [-0.0528968517574562, 1.15190312990417, -0.0989611768448433],
+        [-0.0529716355144438, -0.0980434501171241, 1.15107367264116],
+    ];
+    // the range of exposures, in stops around middle grey, which the curve covers
+    const MIN_EV: f64 = -12.47393;
+    const MAX_EV: f64 = 4.026069;
+
+    let curve = |v: f64| {
+        // the log encoded value in [0, 1]
+        let x = (f64::max(v, 1e-10).log2().clamp(MIN_EV, MAX_EV) - MIN_EV) / (MAX_EV - MIN_EV);
+        // a polynomial fit of the sigmoid
+        let (x2, x4) = (x * x, x * x * x * x);
+        15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x
+            - 0.00232
+    };
+    // the curve gives values encoded with a gamma of 2.2
+    let linear = |v: f64| f64::max(v, 0.0).powf(2.2);
+
+    let c = transform(&INSET, c);
+    let c = transform(&OUTSET, Color::new(curve(c.x), curve(c.y), curve(c.z)));
+    return Color::new(linear(c.x), linear(c.y), linear(c.z));
+}
@@ -0,0 +1,132 @@
+use crate::aabb::Aabb;
+use crate::hit_record::HitRecord;
+use crate::hittable::{area_pdf_to_solid_angle, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use rand::{Rng, RngCore};
+use std::sync::Arc;
+
+// a single flat triangle. the front face is the side from which the vertices appear
+// counter-clockwise.
+pub struct Triangle {
+    pub v0: Point3,
+    pub v1: Point3,
+    pub v2: Point3,
+    pub mat_ptr: Arc<dyn Material + Send + Sync>,
+}
+
+// Möller–Trumbore ray-triangle intersection. returns the ray parameter `t` of the hit,
+// along with the barycentric co-ordinates (b1, b2) of the hit point, which is at
+// (1 - b1 - b2) * v0 + b1 * v1 + b2 * v2
+pub fn intersect_triangle(
+    ray: &Ray,
+    v0: Point3,
+    v1: Point3,
+    v2: Point3,
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, f64, f64)> {
+    let edge1 = v1 - v0;
+    let edge2 = v2 - v0;
+    let pvec = ray.dir.cross(&edge2);
+    let det = edge1.dot(&pvec);
+    // the ray is parallel to the plane of the triangle
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+
+    let tvec = ray.origin - v0;
+    let b1 = tvec.dot(&pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+    let qvec = tvec.cross(&edge1);
+    let b2 = ray.dir.dot(&qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+    let t = edge2.dot(&qvec) * inv_det;
+    if t < t_min || t_max < t {
+        return None;
+    }
+    return Some((t, b1, b2));
+}
+
+// the box around three points, padded so that triangles lying in an axis-aligned plane still
+// have some thickness
+pub fn triangle_bounding_box(v0: Point3, v1: Point3, v2: Point3) -> Aabb {
+    let bbox = Aabb::new(v0, v0).include_point(v1).include_point(v2);
+    let pad = Vec3::new(1e-4, 1e-4, 1e-4);
+    return Aabb::new(bbox.minimum - pad, bbox.maximum + pad);
+}
+
+// the density, per unit solid angle, with which `random_on_triangle` chooses the ray's direction
+pub fn triangle_pdf_value(ray: &Ray, v0: Point3, v1: Point3, v2: Point3) -> f64 {
+    let (t, _, _) = match intersect_triangle(ray, v0, v1, v2, 0.001, f64::INFINITY) {
+        Some(hit) => hit,
+        None => return 0.0,
+    };
+    let normal = (v1 - v0).cross(&(v2 - v0));
+    return area_pdf_to_solid_angle(ray, t, normal, 0.5 * normal.length());
+}
+
+// a direction from `origin` towards a point chosen uniformly over the triangle
+pub fn random_on_triangle(
+    origin: Point3,
+    v0: Point3,
+    v1: Point3,
+    v2: Point3,
+    gen: &mut dyn RngCore,
+) -> Vec3 {
+    // fold the unit square onto the triangle below its diagonal
+    let (mut b1, mut b2) = (gen.gen::<f64>(), gen.gen::<f64>());
+    if b1 + b2 > 1.0 {
+        (b1, b2) = (1.0 - b1, 1.0 - b2);
+    }
+    return v0 + b1 * (v1 - v0) + b2 * (v2 - v0) - origin;
+}
+
+impl Hittable for Triangle {
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+        record: &mut HitRecord,
+        _gen: &mut dyn RngCore,
+    ) -> bool {
+        let (t, b1, b2) = match intersect_triangle(ray, self.v0, self.v1, self.v2, t_min, t_max) {
+            Some(hit) => hit,
+            None => return false,
+        };
+
+        record.t = t;
+        record.p = ray.at(t);
+        record.u = b1;
+        record.v = b2;
+        record.mat_ptr = Some(Arc::clone(&self.mat_ptr));
+        let outward_normal = (self.v1 - self.v0)
+            .cross(&(self.v2 - self.v0))
+            .unit_vector();
+        record.set_face_normal(ray, outward_normal);
+        return true;
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(triangle_bounding_box(self.v0, self.v1, self.v2))
+    }
+
+    fn is_light(&self) -> bool {
+        self.mat_ptr.is_emissive()
+    }
+
+    fn pdf_value(&self, ray: &Ray, _gen: &mut dyn RngCore) -> f64 {
+        triangle_pdf_value(ray, self.v0, self.v1, self.v2)
+    }
+
+    fn random(&self, origin: Point3, gen: &mut dyn RngCore) -> Vec3 {
+        random_on_triangle(origin, self.v0, self.v1, self.v2, gen)
+    }
+}
@@ -1,7 +1,6 @@
-use crate::vec3::Color;
 use std::f64::consts::PI;
 
-fn clamp(x: f64, min: f64, max: f64) -> f64 {
+pub fn clamp(x: f64, min: f64, max: f64) -> f64 {
     if x < min {
         return min;
     }
@@ -15,10 +14,25 @@ pub fn degrees_to_radians(degrees: f64) -> f64 {
     2.0 * PI * degrees / 360.0
 }
 
-pub fn write_color(color: Color, samples_per_pixel: u32, gamma_correction: f64) {
-    let color_scaled = (color / (samples_per_pixel as f64)).powf(gamma_correction);
-    let ir: u32 = (256.0 * clamp(color_scaled.x, 0.0, 0.999)) as u32;
-    let ig: u32 = (256.0 * clamp(color_scaled.y, 0.0, 0.999)) as u32;
-    let ib: u32 = (256.0 * clamp(color_scaled.z, 0.0, 0.999)) as u32;
-    println!("{} {} {}", ir, ig, ib);
+// the real roots of a x^2 + b x + c = 0, smallest first. computed in a form which avoids
+// catastrophic cancellation when one root is much smaller than the other.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Option<(f64, f64)> {
+    if a.abs() < 1e-12 {
+        // the equation is linear
+        if b.abs() < 1e-12 {
+            return None;
+        }
+        let t = -c / b;
+        return Some((t, t));
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let q = -0.5 * (b + f64::copysign(discriminant.sqrt(), b));
+    let (t0, t1) = match q == 0.0 {
+        true => (0.0, 0.0),
+        false => (q / a, c / q),
+    };
+    return Some((f64::min(t0, t1), f64::max(t0, t1)));
 }
@@ -1,6 +1,5 @@
-use rand::rngs::ThreadRng;
 use rand::Rng;
-use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub};
+use std::ops::{Add, AddAssign, Div, DivAssign, Index, Mul, MulAssign, Neg, Sub};
 
 #[derive(Debug, Copy, Clone)]
 pub struct Vec3 {
@@ -25,7 +24,7 @@ impl Vec3 {
         }
     }
 
-    pub fn random(gen: &mut ThreadRng) -> Self {
+    pub fn random<R: Rng + ?Sized>(gen: &mut R) -> Self {
         Self {
             x: gen.gen::<f64>(),
             y: gen.gen::<f64>(),
@@ -66,12 +65,34 @@ impl Vec3 {
         }
     }
 
+    // absolute value element-wise
+    pub fn abs(&self) -> Self {
+        Self {
+            x: self.x.abs(),
+            y: self.y.abs(),
+            z: self.z.abs(),
+        }
+    }
+
     pub fn near_zero(&self) -> bool {
         let s = 1e-8;
         self.x.abs() < s && self.y.abs() < s && self.z.abs() < s
     }
 }
 
+// index the components by axis, with 0, 1, 2 corresponding to x, y, z
+impl Index<usize> for Vec3 {
+    type Output = f64;
+    fn index(&self, axis: usize) -> &f64 {
+        match axis {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Vec3 index out of range: {}", axis),
+        }
+    }
+}
+
 impl Neg for Vec3 {
     type Output = Self;
     fn neg(self) -> Self {
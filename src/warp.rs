@@ -0,0 +1,36 @@
+use crate::vec3::Vec3;
+use std::f64::consts::{FRAC_PI_4, PI};
+
+// functions which map points spread evenly over the unit square onto other shapes, keeping them
+// evenly spread. unlike rejection sampling, each uses exactly one pair of numbers, so the
+// stratification of a sampler's points carries through to the shape.
+
+// a point on the unit disk, using Shirley and Chiu's concentric mapping, which takes squares
+// about the centre of the square to circles about the centre of the disk
+pub fn concentric_disk((u1, u2): (f64, f64)) -> (f64, f64) {
+    let (a, b) = (2.0 * u1 - 1.0, 2.0 * u2 - 1.0);
+    if a == 0.0 && b == 0.0 {
+        return (0.0, 0.0);
+    }
+    let (r, theta) = match a.abs() > b.abs() {
+        true => (a, FRAC_PI_4 * (b / a)),
+        false => (b, 2.0 * FRAC_PI_4 - FRAC_PI_4 * (a / b)),
+    };
+    return (r * theta.cos(), r * theta.sin());
+}
+
+// a direction in the hemisphere about +z, with density proportional to the cosine of its angle
+// to +z (Malley's method: a point on the disk, projected up onto the hemisphere)
+pub fn cosine_hemisphere(u: (f64, f64)) -> Vec3 {
+    let (x, y) = concentric_disk(u);
+    let z = f64::sqrt(f64::max(1.0 - x * x - y * y, 0.0));
+    return Vec3::new(x, y, z);
+}
+
+// a direction spread evenly over the unit sphere
+pub fn uniform_sphere((u1, u2): (f64, f64)) -> Vec3 {
+    let z = 1.0 - 2.0 * u1;
+    let r = f64::sqrt(f64::max(1.0 - z * z, 0.0));
+    let phi = 2.0 * PI * u2;
+    return Vec3::new(r * phi.cos(), r * phi.sin(), z);
+}